@@ -1,7 +1,6 @@
 use anyhow::Error;
 use borsh::BorshDeserialize;
 use pumpfun_amm::{Pool, PoolGlobalConfig};
-use pumpfun_global::{derive_coin_creator_vault_authority, PUMP_FUN_AMM_FEE_RECIPIENT};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
@@ -0,0 +1,310 @@
+//! State of the router itself: who administers it, whether swaps are paused, the platform
+//! fee taken on swaps and the referrers sharing in it.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{Error, TokenAccount};
+
+pub static BPF_LOADER_UPGRADEABLE: Pubkey =
+    Pubkey::from_str_const("BPFLoaderUpgradeab1e11111111111111111111111");
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RouterConfig {
+    pub admin: Pubkey,
+    /// Admin nominated by `TransferAdmin` until it accepts, the default key while none is.
+    pub pending_admin: Pubkey,
+    /// Every swap fails while set.
+    pub paused: bool,
+    /// Taken from the quote input of buys and the quote output of sells.
+    pub platform_fee_bps: u64,
+    /// Owner of the token accounts receiving the platform fee.
+    pub fee_destination: Pubkey,
+    /// Part of the platform fee paid to the referrer of a swap, in basis points of the fee.
+    pub referral_share_bps: u64,
+    pub bump: u8,
+}
+
+impl RouterConfig {
+    pub const DISCRIMINATOR: &[u8] = &[147, 20, 81, 135, 46, 251, 46, 139];
+    /// Account size, discriminator included.
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 8 + 32 + 8 + 1;
+
+    pub fn new(
+        admin: Pubkey,
+        platform_fee_bps: u64,
+        fee_destination: Pubkey,
+        referral_share_bps: u64,
+        bump: u8,
+    ) -> Self {
+        RouterConfig {
+            admin,
+            pending_admin: Pubkey::default(),
+            paused: false,
+            platform_fee_bps,
+            fee_destination,
+            referral_share_bps,
+            bump,
+        }
+    }
+
+    pub fn load(account: &AccountInfo) -> Result<Self, Error> {
+        let config: RouterConfig = load_account(account, Self::DISCRIMINATOR, Self::LEN)?;
+
+        let expected = Pubkey::create_program_address(&[b"config", &[config.bump]], &crate::id())
+            .map_err(|_| Error::InvalidRouterConfigAddress)?;
+        if *account.key != expected {
+            return Err(Error::InvalidRouterConfigAddress);
+        }
+
+        Ok(config)
+    }
+
+    pub fn store(&self, account: &AccountInfo) -> Result<(), Error> {
+        store_account(self, account, Self::DISCRIMINATOR, Self::LEN)
+    }
+
+    pub fn check_admin(&self, admin: &AccountInfo) -> Result<(), Error> {
+        if !admin.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        if *admin.key != self.admin {
+            return Err(Error::InvalidRouterAdmin);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the platform fee, signed by the admin.
+    pub fn update(
+        &mut self,
+        admin: &AccountInfo,
+        platform_fee_bps: u64,
+        fee_destination: Pubkey,
+        referral_share_bps: u64,
+    ) -> Result<(), Error> {
+        self.check_admin(admin)?;
+        check_fees(platform_fee_bps, referral_share_bps)?;
+
+        self.platform_fee_bps = platform_fee_bps;
+        self.fee_destination = fee_destination;
+        self.referral_share_bps = referral_share_bps;
+        Ok(())
+    }
+
+    /// Pauses or resumes every swap, signed by the admin.
+    pub fn set_paused(&mut self, admin: &AccountInfo, paused: bool) -> Result<(), Error> {
+        self.check_admin(admin)?;
+
+        self.paused = paused;
+        Ok(())
+    }
+
+    /// Nominates `new_admin`, signed by the admin. The admin stays in charge until
+    /// [`RouterConfig::accept_admin`], so a mistyped key cannot lock the router.
+    pub fn transfer_admin(&mut self, admin: &AccountInfo, new_admin: Pubkey) -> Result<(), Error> {
+        self.check_admin(admin)?;
+
+        self.pending_admin = new_admin;
+        Ok(())
+    }
+
+    /// Hands the config over to the pending admin, signed by it.
+    pub fn accept_admin(&mut self, new_admin: &AccountInfo) -> Result<(), Error> {
+        if !new_admin.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        if self.pending_admin == Pubkey::default() || *new_admin.key != self.pending_admin {
+            return Err(Error::InvalidPendingAdmin);
+        }
+
+        self.admin = self.pending_admin;
+        self.pending_admin = Pubkey::default();
+        Ok(())
+    }
+
+    pub fn check_active(&self) -> Result<(), Error> {
+        if self.paused {
+            return Err(Error::RouterPaused);
+        }
+
+        Ok(())
+    }
+
+    /// Referrer part of a platform fee of `fee`, the rest goes to the fee destination.
+    pub fn referral_share(&self, fee: u64) -> Result<u64, ProgramError> {
+        (fee as u128)
+            .checked_mul(self.referral_share_bps as u128)
+            .map(|x| x / 10_000)
+            .and_then(|x| u64::try_from(x).ok())
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+}
+
+/// Platform fees a referrer earned in one quote mint, paid straight to its token account.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Referral {
+    pub referrer: Pubkey,
+    pub quote_mint: Pubkey,
+    pub fees_earned: u64,
+    pub bump: u8,
+}
+
+impl Referral {
+    pub const DISCRIMINATOR: &[u8] = &[30, 235, 136, 224, 106, 107, 49, 64];
+    /// Account size, discriminator included.
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+
+    pub fn new(referrer: Pubkey, quote_mint: Pubkey, bump: u8) -> Self {
+        Referral {
+            referrer,
+            quote_mint,
+            fees_earned: 0,
+            bump,
+        }
+    }
+
+    pub fn load(account: &AccountInfo) -> Result<Self, Error> {
+        let referral: Referral = load_account(account, Self::DISCRIMINATOR, Self::LEN)?;
+
+        let expected = Pubkey::create_program_address(
+            &[
+                b"referral",
+                referral.referrer.as_ref(),
+                referral.quote_mint.as_ref(),
+                &[referral.bump],
+            ],
+            &crate::id(),
+        )
+        .map_err(|_| Error::InvalidReferralAddress)?;
+        if *account.key != expected {
+            return Err(Error::InvalidReferralAddress);
+        }
+
+        Ok(referral)
+    }
+
+    pub fn store(&self, account: &AccountInfo) -> Result<(), Error> {
+        store_account(self, account, Self::DISCRIMINATOR, Self::LEN)
+    }
+
+    /// Fails unless `referrer_ata` is a `quote_mint` account of the referrer and the referrer
+    /// is not `user`, who would otherwise get a share of its own fee back.
+    pub fn check_referrer(
+        &self,
+        quote_mint: &Pubkey,
+        referrer_ata: &TokenAccount,
+        user: &Pubkey,
+    ) -> Result<(), Error> {
+        if self.quote_mint != *quote_mint
+            || referrer_ata.mint != self.quote_mint
+            || referrer_ata.owner != self.referrer
+        {
+            return Err(Error::InvalidReferral);
+        }
+
+        if self.referrer == *user {
+            return Err(Error::SelfReferral);
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn load_account<T: BorshDeserialize>(
+    account: &AccountInfo,
+    discriminator: &[u8],
+    len: usize,
+) -> Result<T, Error> {
+    if *account.owner != crate::id() {
+        return Err(Error::InvalidAccountOwner);
+    }
+
+    let data = account.data.as_ref().borrow();
+    if data.len() < len {
+        return Err(Error::ProgramError(ProgramError::InvalidArgument));
+    }
+
+    if &data[..8] != discriminator {
+        return Err(Error::InvalidAccountDiscriminator);
+    }
+
+    T::try_from_slice(&data[8..len]).map_err(Error::BorshIoError)
+}
+
+pub(crate) fn store_account<T: BorshSerialize>(
+    value: &T,
+    account: &AccountInfo,
+    discriminator: &[u8],
+    len: usize,
+) -> Result<(), Error> {
+    let mut data = account.data.as_ref().borrow_mut();
+    if data.len() < len {
+        return Err(Error::ProgramError(ProgramError::AccountDataTooSmall));
+    }
+
+    data[..8].copy_from_slice(discriminator);
+    value
+        .serialize(&mut &mut data[8..len])
+        .map_err(Error::BorshIoError)
+}
+
+/// Fails unless the platform fee is below 100% and the referral share at most the whole fee.
+pub fn check_fees(platform_fee_bps: u64, referral_share_bps: u64) -> Result<(), Error> {
+    if platform_fee_bps >= 10_000 {
+        return Err(Error::InvalidPlatformFee);
+    }
+
+    if referral_share_bps > 10_000 {
+        return Err(Error::InvalidReferralShare);
+    }
+
+    Ok(())
+}
+
+pub fn derive_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &crate::id())
+}
+
+pub fn derive_referral(referrer: &Pubkey, quote_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"referral", referrer.as_ref(), quote_mint.as_ref()],
+        &crate::id(),
+    )
+}
+
+pub fn derive_program_data() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[crate::id().as_ref()], &BPF_LOADER_UPGRADEABLE)
+}
+
+/// Upgrade authority recorded in the router program data account, `None` once the program is
+/// immutable.
+pub fn upgrade_authority(program_data: &AccountInfo) -> Result<Option<Pubkey>, Error> {
+    // `UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address:
+    // Option<Pubkey> }`, bincode encoded after a u32 variant index
+    const PROGRAM_DATA_VARIANT: u32 = 3;
+
+    if *program_data.owner != BPF_LOADER_UPGRADEABLE {
+        return Err(Error::InvalidAccountOwner);
+    }
+
+    if *program_data.key != derive_program_data().0 {
+        return Err(Error::InvalidProgramDataAddress);
+    }
+
+    let data = program_data.data.as_ref().borrow();
+    if data.len() < 45 || data[..4] != PROGRAM_DATA_VARIANT.to_le_bytes() {
+        return Err(Error::InvalidAccountDiscriminator);
+    }
+
+    match data[12] {
+        0 => Ok(None),
+        _ => Ok(Some(Pubkey::new_from_array(
+            data[13..45]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        ))),
+    }
+}
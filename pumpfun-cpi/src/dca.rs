@@ -0,0 +1,101 @@
+//! Dollar cost averaging vaults: the router escrows a quote budget and anyone may crank one
+//! buy of a fixed size per interval into the owner token account.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    ceil_div,
+    config::{load_account, store_account},
+    Error, PRICE_SCALE,
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Dca {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    /// Quote spent by each crank, the last one spends whatever is left of the budget.
+    pub amount_per_interval: u64,
+    pub interval_slots: u64,
+    /// Slippage of each buy, see [`crate::BuyInstruction`].
+    pub slippage_bps: u64,
+    /// Quote per base scaled by [`PRICE_SCALE`], the most any buy pays. Unlike the slippage it
+    /// does not follow the reserves, which the cranker can move.
+    pub max_price: u128,
+    /// First slot the next buy can be cranked in.
+    pub next_fill_slot: u64,
+    pub nonce: u64,
+    pub bump: u8,
+    pub authority_bump: u8,
+}
+
+impl Dca {
+    pub const DISCRIMINATOR: &[u8] = &[82, 93, 90, 127, 40, 101, 145, 154];
+    /// Account size, discriminator included.
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 1;
+
+    pub fn load(account: &AccountInfo) -> Result<Self, Error> {
+        let dca: Dca = load_account(account, Self::DISCRIMINATOR, Self::LEN)?;
+
+        let expected = Pubkey::create_program_address(
+            &[
+                b"dca",
+                dca.owner.as_ref(),
+                &dca.nonce.to_le_bytes(),
+                &[dca.bump],
+            ],
+            &crate::id(),
+        )
+        .map_err(|_| Error::InvalidDcaAddress)?;
+        if *account.key != expected {
+            return Err(Error::InvalidDcaAddress);
+        }
+
+        Ok(dca)
+    }
+
+    pub fn store(&self, account: &AccountInfo) -> Result<(), Error> {
+        store_account(self, account, Self::DISCRIMINATOR, Self::LEN)
+    }
+
+    /// Quote spent by the next buy out of the `escrowed` budget left.
+    pub fn next_amount(&self, escrowed: u64) -> Result<u64, Error> {
+        match self.amount_per_interval.min(escrowed) {
+            0 => Err(Error::DcaCompleted),
+            amount => Ok(amount),
+        }
+    }
+
+    /// Least base a buy of `amount` quote receives at the max price.
+    pub fn min_output(&self, amount: u64) -> Result<u64, ProgramError> {
+        let num = (amount as u128)
+            .checked_mul(PRICE_SCALE)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        u64::try_from(ceil_div(num, self.max_price)?).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    /// Fails until the interval since the last buy has elapsed, then schedules the next one.
+    pub fn advance(&mut self, slot: u64) -> Result<(), Error> {
+        if slot < self.next_fill_slot {
+            return Err(Error::DcaNotDue);
+        }
+
+        // Counted from the crank rather than the schedule, so a late crank does not allow a
+        // burst of buys
+        self.next_fill_slot = slot.saturating_add(self.interval_slots);
+        Ok(())
+    }
+}
+
+pub fn derive_dca(owner: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"dca", owner.as_ref(), &nonce.to_le_bytes()],
+        &crate::id(),
+    )
+}
+
+/// Owner of the DCA escrow and the user of the buys.
+pub fn derive_dca_authority(dca: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"dca-authority", dca.as_ref()], &crate::id())
+}
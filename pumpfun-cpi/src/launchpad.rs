@@ -0,0 +1,357 @@
+//! State, math and instructions of the Pumpfun launchpad, where tokens trade on a bonding
+//! curve until it completes and the liquidity migrates to PumpSwap.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{ceil_div, Error, Fees, PUMPFUN_LAUNCHPAD_PROGRAM};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct BoundingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    pub creator: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Global {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+    pub withdraw_authority: Pubkey,
+    pub enable_migrate: bool,
+    pub pool_migration_fee: u64,
+    pub creator_fee_basis_points: u64,
+    pub fee_recipients: [Pubkey; 7],
+    pub set_creator_authority: Pubkey,
+}
+
+impl Global {
+    /// Every fee recipient the launchpad accepts.
+    pub fn fee_recipients(&self) -> impl Iterator<Item = &Pubkey> {
+        std::iter::once(&self.fee_recipient).chain(self.fee_recipients.iter())
+    }
+}
+
+pub fn derive_global() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global"], &PUMPFUN_LAUNCHPAD_PROGRAM)
+}
+
+pub fn derive_bounding_curve(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"bonding-curve", mint.as_ref()],
+        &PUMPFUN_LAUNCHPAD_PROGRAM,
+    )
+}
+
+pub fn derive_creator_vault(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"creator-vault", creator.as_ref()],
+        &PUMPFUN_LAUNCHPAD_PROGRAM,
+    )
+}
+
+/// Bonding curve of a single mint together with the launchpad global fee settings.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct CurveData {
+    pub curve: BoundingCurve,
+    pub global: Global,
+}
+
+impl CurveData {
+    const BONDING_CURVE_DISCRIMINATOR: &[u8] = &[23, 183, 248, 55, 96, 216, 172, 96];
+    const GLOBAL_DISCRIMINATOR: &[u8] = &[167, 232, 232, 177, 200, 108, 114, 127];
+
+    pub(crate) fn new(
+        curve_account: &AccountInfo,
+        global_account: &AccountInfo,
+        mint: &Pubkey,
+    ) -> Result<CurveData, Error> {
+        Self::check_account(curve_account, Self::BONDING_CURVE_DISCRIMINATOR, 81)?;
+        let (expected_curve, _) = derive_bounding_curve(mint);
+        if *curve_account.key != expected_curve {
+            return Err(Error::InvalidBondingCurveAddress);
+        }
+
+        let curve = BoundingCurve::try_from_slice(&curve_account.data.as_ref().borrow()[8..81])
+            .map_err(Error::BorshIoError)?;
+
+        Self::check_account(global_account, Self::GLOBAL_DISCRIMINATOR, 418)?;
+        let (expected_global, _) = derive_global();
+        if *global_account.key != expected_global {
+            return Err(Error::InvalidLaunchpadGlobalAddress);
+        }
+
+        let global = Global::try_from_slice(&global_account.data.as_ref().borrow()[8..418])
+            .map_err(Error::BorshIoError)?;
+
+        Ok(CurveData { curve, global })
+    }
+
+    /// Reads the curve reserves back from `curve_account`, used once a swap has moved them.
+    pub(crate) fn read_curve(curve_account: &AccountInfo) -> Result<BoundingCurve, Error> {
+        Self::check_account(curve_account, Self::BONDING_CURVE_DISCRIMINATOR, 81)?;
+        BoundingCurve::try_from_slice(&curve_account.data.as_ref().borrow()[8..81])
+            .map_err(Error::BorshIoError)
+    }
+
+    fn check_account(account: &AccountInfo, discriminator: &[u8], len: usize) -> Result<(), Error> {
+        if *account.owner != PUMPFUN_LAUNCHPAD_PROGRAM {
+            return Err(Error::InvalidAccountOwner);
+        }
+
+        let data = account.data.as_ref().borrow();
+        if data.len() < len {
+            return Err(Error::ProgramError(ProgramError::InvalidArgument));
+        }
+
+        if &data[..8] != discriminator {
+            return Err(Error::InvalidAccountDiscriminator);
+        }
+
+        Ok(())
+    }
+
+    /// Tokens received for spending `sol_amount_in` lamports, fees included.
+    pub fn base_out(&self, sol_amount_in: u64) -> Result<u64, ProgramError> {
+        // Effective sol in amount
+        //
+        // sol = sol_amount_in * 10000 / (10000 + total_fee_bp)
+        let total_fee_bp = self
+            .global
+            .fee_basis_points
+            .checked_add(self.creator_fee_basis_points())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let mut sol = (sol_amount_in as u128)
+            .checked_mul(10000u128)
+            .and_then(|x| x.checked_div(10000u128 + total_fee_bp as u128))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        loop {
+            // base_out = (virtual_token_reserves * sol) / (virtual_sol_reserves + sol)
+            let num = (self.curve.virtual_token_reserves as u128)
+                .checked_mul(sol)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            let denom = (self.curve.virtual_sol_reserves as u128)
+                .checked_add(sol)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            let base_out = num
+                .checked_div(denom)
+                .ok_or(ProgramError::ArithmeticOverflow)? as u64;
+
+            // The curve can not sell more than it holds
+            let base_out = base_out.min(self.curve.real_token_reserves);
+            if base_out == 0 {
+                return Ok(0);
+            }
+
+            // The launchpad rounds the cost of `base_out` up, shrink the effective sol by the
+            // excess until the buy fits into `sol_amount_in`
+            let (cost, _) = self.quote_in(base_out)?;
+            match cost.checked_sub(sol_amount_in) {
+                Some(excess) if excess > 0 => sol = sol.saturating_sub(excess as u128),
+                _ => return Ok(base_out),
+            }
+        }
+    }
+
+    /// Lamports the launchpad charges for exactly `base_amount_out` tokens, fees included,
+    /// with the fee breakdown.
+    pub fn quote_in(&self, base_amount_out: u64) -> Result<(u64, Fees), ProgramError> {
+        // sol_in = (virtual_sol_reserves * base) / (virtual_token_reserves - base) + 1
+        let num = (self.curve.virtual_sol_reserves as u128)
+            .checked_mul(base_amount_out as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let denom = self
+            .curve
+            .virtual_token_reserves
+            .checked_sub(base_amount_out)
+            .filter(|denom| *denom > 0)
+            .ok_or(ProgramError::ArithmeticOverflow)? as u128;
+
+        let sol_in =
+            u64::try_from(num / denom + 1).map_err(|_| ProgramError::ArithmeticOverflow)?;
+
+        let fees = self.fees(sol_in)?;
+        let total = sol_in
+            .checked_add(fees.total()?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        Ok((total, fees))
+    }
+
+    /// Lamports received for `base_amount_in` tokens, fees excluded.
+    pub fn quote_out(&self, base_amount_in: u64) -> Result<u64, ProgramError> {
+        let sol_out = self.quote_out_before_fees(base_amount_in)?;
+
+        let fees = self.fees(sol_out)?;
+        sol_out
+            .checked_sub(fees.total()?)
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+
+    /// Lamports released by the curve for `base_amount_in`, the amount sell fees are charged on.
+    pub fn quote_out_before_fees(&self, base_amount_in: u64) -> Result<u64, ProgramError> {
+        // sol_out = (virtual_sol_reserves * base) / (virtual_token_reserves + base)
+        let num = (self.curve.virtual_sol_reserves as u128)
+            .checked_mul(base_amount_in as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let denom = (self.curve.virtual_token_reserves as u128)
+            .checked_add(base_amount_in as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let sol_out = num
+            .checked_div(denom)
+            .ok_or(ProgramError::ArithmeticOverflow)? as u64;
+
+        Ok(sol_out)
+    }
+
+    /// Breakdown of the fees charged on a lamports `amount`, the launchpad has no LP fee.
+    pub fn fees(&self, amount: u64) -> Result<Fees, ProgramError> {
+        let fee = |bps: u64| -> Result<u64, ProgramError> {
+            let num = (amount as u128)
+                .checked_mul(bps as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok(ceil_div(num, 10000u128)? as u64)
+        };
+
+        Ok(Fees {
+            lp_fee: 0,
+            protocol_fee: fee(self.global.fee_basis_points)?,
+            coin_creator_fee: fee(self.creator_fee_basis_points())?,
+        })
+    }
+
+    /// Curves without a creator are not charged the creator fee.
+    fn creator_fee_basis_points(&self) -> u64 {
+        if self.curve.creator == Pubkey::default() {
+            return 0;
+        }
+
+        self.global.creator_fee_basis_points
+    }
+}
+
+pub(crate) mod cpi {
+    use borsh::BorshSerialize;
+    use solana_instruction::{AccountMeta, Instruction};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::{Error, PUMPFUN_LAUNCHPAD_PROGRAM};
+
+    const BUY_DISCRIMINATOR: &[u8] = &[102, 6, 61, 18, 1, 218, 235, 234];
+    const SELL_DISCRIMINATOR: &[u8] = &[51, 230, 133, 164, 1, 127, 131, 173];
+
+    #[derive(BorshSerialize, Debug)]
+    pub struct Buy {
+        pub amount: u64,
+        pub max_sol_cost: u64,
+    }
+
+    #[derive(BorshSerialize, Debug)]
+    pub struct Sell {
+        pub amount: u64,
+        pub min_sol_output: u64,
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn buy(
+        global: &Pubkey,
+        fee_recipient: &Pubkey,
+        mint: &Pubkey,
+        bonding_curve: &Pubkey,
+        associated_bonding_curve: &Pubkey,
+        user_ata: &Pubkey,
+        user: &Pubkey,
+        system_program: &Pubkey,
+        token_program: &Pubkey,
+        creator_vault: &Pubkey,
+        event_authority: &Pubkey,
+        launchpad_program: &Pubkey,
+        global_volume_accumulator: &Pubkey,
+        user_volume_accumulator: &Pubkey,
+        instruction: Buy,
+    ) -> Result<Instruction, Error> {
+        let accounts = vec![
+            AccountMeta::new_readonly(*global, false),
+            AccountMeta::new(*fee_recipient, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*bonding_curve, false),
+            AccountMeta::new(*associated_bonding_curve, false),
+            AccountMeta::new(*user_ata, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(*system_program, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new(*creator_vault, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*launchpad_program, false),
+            AccountMeta::new(*global_volume_accumulator, false),
+            AccountMeta::new(*user_volume_accumulator, false),
+        ];
+
+        let mut data = Vec::from(BUY_DISCRIMINATOR);
+        BorshSerialize::serialize(&instruction, &mut data).map_err(Error::BorshIoError)?;
+
+        Ok(Instruction {
+            program_id: PUMPFUN_LAUNCHPAD_PROGRAM,
+            accounts,
+            data,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn sell(
+        global: &Pubkey,
+        fee_recipient: &Pubkey,
+        mint: &Pubkey,
+        bonding_curve: &Pubkey,
+        associated_bonding_curve: &Pubkey,
+        user_ata: &Pubkey,
+        user: &Pubkey,
+        system_program: &Pubkey,
+        creator_vault: &Pubkey,
+        token_program: &Pubkey,
+        event_authority: &Pubkey,
+        launchpad_program: &Pubkey,
+        instruction: Sell,
+    ) -> Result<Instruction, Error> {
+        let accounts = vec![
+            AccountMeta::new_readonly(*global, false),
+            AccountMeta::new(*fee_recipient, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*bonding_curve, false),
+            AccountMeta::new(*associated_bonding_curve, false),
+            AccountMeta::new(*user_ata, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(*system_program, false),
+            AccountMeta::new(*creator_vault, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*launchpad_program, false),
+        ];
+
+        let mut data = Vec::from(SELL_DISCRIMINATOR);
+        BorshSerialize::serialize(&instruction, &mut data).map_err(Error::BorshIoError)?;
+
+        Ok(Instruction {
+            program_id: PUMPFUN_LAUNCHPAD_PROGRAM,
+            accounts,
+            data,
+        })
+    }
+}
@@ -1,35 +1,725 @@
+// Clients build without the entrypoint, leaving the CPI and account helpers of the handlers unused
+#![cfg_attr(feature = "no-entrypoint", allow(dead_code))]
+
+use std::slice::Iter;
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, declare_id, msg, program_error::ProgramError, pubkey::Pubkey,
+    account_info::{next_account_info, AccountInfo},
+    declare_id,
+    log::sol_log_data,
+    program::set_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
 };
 
+pub mod config;
+pub mod dca;
+pub mod launchpad;
+pub mod order;
+
 declare_id!("6dXexJ3SwyRcmdRiqYMTURDx3AX7BTLaHa6ei9bSTEAz");
 
+pub static PUMPFUN_AMM_PROGRAM: Pubkey =
+    Pubkey::from_str_const("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA");
+
+pub static PUMPFUN_LAUNCHPAD_PROGRAM: Pubkey =
+    Pubkey::from_str_const("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+
+pub static SYSTEM_PROGRAM: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
+
+pub static SPL_TOKEN_PROGRAM: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+pub static SPL_TOKEN_2022_PROGRAM: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+pub static ASSOCIATED_TOKEN_PROGRAM: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub static NATIVE_MINT: Pubkey =
+    Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub enum Instructions {
     ExecuteSwap(BuyInstruction),
+    ExecuteSell(SellInstruction),
+    Quote(QuoteInstruction),
+    BuyExactOut(BuyExactOutInstruction),
+    SmartBuy(SmartBuyInstruction),
+    SmartSell(SmartSellInstruction),
+    CurveBuy(CurveBuyInstruction),
+    CurveSell(CurveSellInstruction),
+    RouteSwap(RouteSwapInstruction),
+    SplitBuy(SplitBuyInstruction),
+    Arbitrage(ArbitrageInstruction),
+    Initialize(InitializeInstruction),
+    UpdateConfig(UpdateConfigInstruction),
+    SetPaused(SetPausedInstruction),
+    TransferAdmin(TransferAdminInstruction),
+    /// Creates the referral of the signing referrer for one quote mint. Accounts are the
+    /// referral, the referrer paying for it, the quote mint and the system program.
+    RegisterReferral,
+    PlaceOrder(PlaceOrderInstruction),
+    /// Fills an order whose limit the pool price crossed, paying the keeper tip. Accounts are
+    /// the order, its owner, the keeper, the owner base and quote token accounts, then the
+    /// router `ExecuteSwap` accounts for a buy order or `ExecuteSell` accounts for a sell order
+    /// with the order authority as the user, the fee vault and the router config last.
+    FillOrder,
+    /// Returns the escrow and the order rent to the owner. Accounts are the order, the order
+    /// authority, the owner, the input token program, the input mint, the owner input token
+    /// account, the escrow, the output token program, the output mint, the owner output token
+    /// account and the authority output token account.
+    CancelOrder,
+    CreateDca(CreateDcaInstruction),
+    /// Buys `amount_per_interval` of quote out of a DCA escrow once its interval elapsed and
+    /// sends the base to the owner. Accounts are the DCA, its owner, the owner base token
+    /// account, then the router `ExecuteSwap` accounts with the DCA authority as the user, the
+    /// fee vault and the router config last.
+    CrankDca,
+    /// Returns the quote left in the escrow and the DCA rent to the owner. Accounts are the
+    /// DCA, the DCA authority, the owner, the quote token program, the quote mint, the owner
+    /// quote token account, the escrow, the base token program, the base mint, the owner base
+    /// token account and the authority base token account.
+    CloseDca,
+    /// Takes the config over after [`TransferAdminInstruction`] nominated the signer. Accounts
+    /// are the config and the new admin.
+    AcceptAdmin,
 }
 
+/// Buys on a PumpSwap pool. Accounts are the [`SwapAccounts`], the router `ExecuteSwap`
+/// accounts other instructions refer to, then the platform fee accounts and the router config
+/// last.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct BuyInstruction {
     /// `max_quote_amount_in` in the Pumpfun `buy` instruction.
     input_amount: u64,
     slippage_bps: u64,
+    /// Minimum base the user token account must receive, measured after the swap.
+    min_base_out: u64,
+    /// Pay with lamports, see [`BuyInstruction::with_native_sol`].
+    native_sol: bool,
+    /// Last slot the swap may land in, see [`BuyInstruction::with_valid_until_slot`].
+    valid_until_slot: Option<u64>,
+    /// Reserves the client quoted against, see [`BuyInstruction::with_expected_reserves`].
+    expected_reserves: Option<ExpectedReserves>,
+    /// See [`BuyInstruction::with_max_price_impact_bps`].
+    max_price_impact_bps: Option<u64>,
+    /// See [`BuyInstruction::with_referrer`].
+    referrer: bool,
 }
 
-#[derive(Debug)]
-pub struct SwapPerformed {
+/// Pool reserves a client quoted against and how far the live reserves may drift from them.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpectedReserves {
+    reserve_base: u64,
+    reserve_quote: u64,
+    /// Allowed drift of either reserve, in basis points of the expected reserve.
+    tolerance_bps: u64,
+}
+
+/// Buys exactly `base_amount_out`, spending at most the fee inclusive quote cost plus
+/// `slippage_bps`. Accounts are the router `ExecuteSwap` accounts, then the platform fee
+/// accounts and the router config last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct BuyExactOutInstruction {
+    /// `base_amount_out` in the Pumpfun `buy` instruction.
+    base_amount_out: u64,
+    slippage_bps: u64,
+    native_sol: bool,
+    /// See [`BuyExactOutInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Sells on a PumpSwap pool. Accounts are the [`SwapAccounts`], the router `ExecuteSell`
+/// accounts other instructions refer to, then the platform fee accounts and the router config
+/// last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SellInstruction {
+    /// `base_amount_in` in the Pumpfun `sell` instruction.
+    input_amount: u64,
+    slippage_bps: u64,
+    /// Minimum quote the user token account must receive, measured after the swap.
+    min_quote_out: u64,
+    /// Receive lamports, see [`SellInstruction::with_native_sol`].
+    native_sol: bool,
+    /// See [`SellInstruction::with_max_price_impact_bps`].
+    max_price_impact_bps: Option<u64>,
+    /// See [`SellInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Buys with lamports on the bonding curve while it is active and on PumpSwap once it has
+/// completed, where the user WSOL account must be empty. Accounts are the launchpad `buy`
+/// accounts followed by the associated token program, the router `ExecuteSwap` accounts, then
+/// the platform fee accounts and the router config last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SmartBuyInstruction {
+    /// Lamports to spend, fees included.
+    input_amount: u64,
+    slippage_bps: u64,
+    min_base_out: u64,
+    /// See [`SmartBuyInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Sells for lamports on the bonding curve while it is active and on PumpSwap once it has
+/// completed, where the user WSOL account must be empty. Accounts are the launchpad `sell`
+/// accounts, the router `ExecuteSell` accounts, then the platform fee accounts and the router
+/// config last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SmartSellInstruction {
+    input_amount: u64,
+    slippage_bps: u64,
+    /// Minimum lamports received.
+    min_quote_out: u64,
+    /// See [`SmartSellInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Buys with lamports on the bonding curve. Accounts are the launchpad `buy` accounts followed
+/// by the associated token program, then the platform fee accounts and the router config last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CurveBuyInstruction {
+    /// `max_sol_cost` in the launchpad `buy` instruction.
+    input_amount: u64,
+    slippage_bps: u64,
+    min_base_out: u64,
+    /// See [`CurveBuyInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Sells for lamports on the bonding curve. Accounts are the launchpad `sell` accounts, then
+/// the platform fee accounts and the router config last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CurveSellInstruction {
+    /// `amount` in the launchpad `sell` instruction.
+    input_amount: u64,
+    slippage_bps: u64,
+    min_quote_out: u64,
+    /// See [`CurveSellInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Swaps through PumpSwap pools one after another, each hop spending everything the previous
+/// one received. Accounts are the router `ExecuteSwap` accounts for a [`Direction::Buy`] hop and
+/// the `ExecuteSell` accounts for a [`Direction::Sell`] hop, concatenated in hop order, then the
+/// platform fee accounts once and the router config last. The first hop pays the platform fee.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RouteSwapInstruction {
+    /// Input of the first hop.
+    input_amount: u64,
+    /// Minimum the user must receive from the last hop.
+    min_output: u64,
+    hops: Vec<Direction>,
+    /// See [`RouteSwapInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Buys across several pools of the same pair, splitting the input as computed by
+/// [`split_buy`]. Accounts are the router `ExecuteSwap` accounts of every pool, one after
+/// another, then the platform fee accounts once and the router config last.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SplitBuyInstruction {
+    /// Total quote to spend across all pools.
+    input_amount: u64,
+    slippage_bps: u64,
+    /// Minimum base received across all pools.
+    min_base_out: u64,
+    pools: u8,
+    /// See [`SplitBuyInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Buys on one venue and sells everything bought on the other, reverting unless the user ends
+/// up with at least `min_profit` more quote. Accounts are the buy venue accounts followed by the
+/// sell venue accounts: the router `ExecuteSwap` / `ExecuteSell` accounts for a pool, the
+/// `CurveBuy` / `CurveSell` accounts for the bonding curve, then the platform fee accounts
+/// once and the router config last. The buy venue pays the platform fee.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ArbitrageInstruction {
+    /// Quote spent on the buy venue.
     input_amount: u64,
-    output_amount: u64,
+    /// Quote balance increase required in the user quote token account, plus the user lamports
+    /// when a venue is the curve. Rent of accounts created on the curve route counts against it.
+    min_profit: u64,
+    buy_venue: Venue,
+    sell_venue: Venue,
+    /// See [`ArbitrageInstruction::with_referrer`].
+    referrer: bool,
+}
+
+/// Creates the router config. Accounts are the config, the admin paying for it, who has to be
+/// the router upgrade authority, the router program data and the system program.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct InitializeInstruction {
+    platform_fee_bps: u64,
+    fee_destination: Pubkey,
+    referral_share_bps: u64,
+}
+
+/// Replaces the platform fee. Accounts are the config and the admin.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct UpdateConfigInstruction {
+    platform_fee_bps: u64,
+    fee_destination: Pubkey,
+    referral_share_bps: u64,
+}
+
+/// Pauses or resumes every swap. Accounts are the config and the admin.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetPausedInstruction {
+    paused: bool,
+}
+
+/// Nominates `new_admin`, who takes the config over by signing `AcceptAdmin`. Accounts are the
+/// config and the current admin.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TransferAdminInstruction {
+    new_admin: Pubkey,
+}
+
+/// Escrows `amount_in` of pool quote for a buy order, or base for a sell order, until a keeper
+/// fills it or the owner cancels it. Accounts are the order, the order authority, the owner,
+/// the pool, the input mint, the input token program, the owner input token account, the
+/// escrow, the output mint, the output token program, the authority output token account, the
+/// system program and the associated token program, then for a buy order the PumpSwap volume
+/// accumulator of the order authority, the PumpSwap event authority and the PumpSwap program.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PlaceOrderInstruction {
+    /// Tells apart the orders of one owner.
+    nonce: u64,
+    direction: Direction,
+    amount_in: u64,
+    /// See [`order::Order::limit_price`].
+    limit_price: u128,
+    expires_at_slot: u64,
+    /// Lamports paid to the keeper filling the order.
+    keeper_tip: u64,
+}
+
+/// Escrows a quote `budget` spent in buys of `amount_per_interval` at most every
+/// `interval_slots`, none paying more than `max_price`. Accounts are the DCA, the DCA
+/// authority, the owner, the pool, the quote mint, the quote token program, the owner quote
+/// token account, the escrow, the base mint, the base token program, the authority base token
+/// account, the system program, the associated token program, the PumpSwap volume accumulator
+/// of the DCA authority, the PumpSwap event authority and the PumpSwap program.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreateDcaInstruction {
+    /// Tells apart the DCAs of one owner.
+    nonce: u64,
+    budget: u64,
+    amount_per_interval: u64,
+    interval_slots: u64,
+    /// Slippage of each buy, see [`BuyInstruction`].
+    slippage_bps: u64,
+    /// See [`dca::Dca::max_price`].
+    max_price: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Venue {
+    /// PumpSwap pool, quote held in the user quote token account.
+    Pool,
+    /// Launchpad bonding curve, quote held as lamports of the user.
+    Curve,
+}
+
+/// Read-only quote against the live pool state, the result is returned as a [`QuoteResult`]
+/// through the return data.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct QuoteInstruction {
+    /// Quote in for [`Direction::Buy`], base in for [`Direction::Sell`].
+    pub amount: u64,
+    pub direction: Direction,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Quote in, base out.
+    Buy,
+    /// Base in, quote out.
+    Sell,
+}
+
+/// Event emitted through `sol_log_data` after every swap, the log line is the Anchor style
+/// `Program data: base64(SwapPerformed::DISCRIMINATOR || borsh(SwapPerformed))`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwapPerformed {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub direction: Direction,
+    /// Amount that left the user input token account.
+    pub input_amount: u64,
+    /// Amount that landed in the user output token account.
+    pub output_amount: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub coin_creator_fee: u64,
+    /// Pool reserves after the swap.
+    pub reserve_base: u64,
+    pub reserve_quote: u64,
+}
+
+impl SwapPerformed {
+    /// `sha256("event:SwapPerformed")[..8]`
+    pub const DISCRIMINATOR: [u8; 8] = [6, 144, 130, 26, 181, 50, 153, 39];
+
+    pub fn emit(&self) -> Result<(), Error> {
+        let mut data = Vec::from(Self::DISCRIMINATOR);
+        BorshSerialize::serialize(self, &mut data).map_err(Error::BorshIoError)?;
+        sol_log_data(&[&data]);
+
+        Ok(())
+    }
+
+    /// Logs the event and sets the matching [`SwapResult`] as the instruction return data.
+    pub fn publish(&self) -> Result<(), Error> {
+        self.emit()?;
+        SwapResult::from(self).set_return_data()
+    }
+
+    /// Decodes the payload of a `Program data:` log line, `None` if it is not a `SwapPerformed`.
+    pub fn decode(data: &[u8]) -> Option<SwapPerformed> {
+        let payload = data.strip_prefix(&Self::DISCRIMINATOR)?;
+        SwapPerformed::try_from_slice(payload).ok()
+    }
 }
 
 impl BuyInstruction {
-    pub fn new(input_amount: u64, slippage_bps: u64) -> Self {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_base_out: u64) -> Self {
         BuyInstruction {
             input_amount,
             slippage_bps,
+            min_base_out,
+            native_sol: false,
+            valid_until_slot: None,
+            expected_reserves: None,
+            max_price_impact_bps: None,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee to the referrer token account and the
+    /// referral following the fee vault.
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+
+    /// Spends `input_amount` lamports instead of WSOL. The router wraps them into the user
+    /// WSOL account before the swap and closes that account back to the user afterwards, so
+    /// the swap fails if the account already holds WSOL.
+    pub fn with_native_sol(mut self) -> Self {
+        self.native_sol = true;
+        self
+    }
+
+    /// Fails the swap once the current slot is past `slot`.
+    pub fn with_valid_until_slot(mut self, slot: u64) -> Self {
+        self.valid_until_slot = Some(slot);
+        self
+    }
+
+    /// Fails with [`Error::SwapExpired`] when `slot` is past the valid until slot.
+    pub fn check_valid_until(&self, slot: u64) -> Result<(), Error> {
+        match self.valid_until_slot {
+            Some(valid_until_slot) if slot > valid_until_slot => {
+                solana_program::msg!(
+                    "Slot {} is past the valid until slot {}",
+                    slot,
+                    valid_until_slot
+                );
+                Err(Error::SwapExpired)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails the swap when either pool reserve is more than `tolerance_bps` away from the
+    /// reserves the client quoted against, e.g. after a sandwich front-run.
+    pub fn with_expected_reserves(
+        mut self,
+        reserve_base: u64,
+        reserve_quote: u64,
+        tolerance_bps: u64,
+    ) -> Self {
+        self.expected_reserves = Some(ExpectedReserves::new(
+            reserve_base,
+            reserve_quote,
+            tolerance_bps,
+        ));
+        self
+    }
+
+    /// Fails the swap when the quoted execution price is more than `max_price_impact_bps`
+    /// above the pool spot price.
+    pub fn with_max_price_impact_bps(mut self, max_price_impact_bps: u64) -> Self {
+        self.max_price_impact_bps = Some(max_price_impact_bps);
+        self
+    }
+}
+
+impl ExpectedReserves {
+    pub fn new(reserve_base: u64, reserve_quote: u64, tolerance_bps: u64) -> Self {
+        ExpectedReserves {
+            reserve_base,
+            reserve_quote,
+            tolerance_bps,
+        }
+    }
+}
+
+impl BuyExactOutInstruction {
+    pub fn new(base_amount_out: u64, slippage_bps: u64) -> Self {
+        BuyExactOutInstruction {
+            base_amount_out,
+            slippage_bps,
+            native_sol: false,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+
+    /// Pays with lamports, only the maximum quote in is wrapped and the rest is returned
+    /// when the WSOL account is closed. Fails if the user WSOL account already holds WSOL.
+    pub fn with_native_sol(mut self) -> Self {
+        self.native_sol = true;
+        self
+    }
+}
+
+impl SellInstruction {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_quote_out: u64) -> Self {
+        SellInstruction {
+            input_amount,
+            slippage_bps,
+            min_quote_out,
+            native_sol: false,
+            max_price_impact_bps: None,
+            referrer: false,
         }
     }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+
+    /// Receives lamports instead of WSOL, the user WSOL account is closed after the swap.
+    /// Fails if the account already holds WSOL, which closing it would unwrap too.
+    pub fn with_native_sol(mut self) -> Self {
+        self.native_sol = true;
+        self
+    }
+
+    /// Fails the swap when the quoted execution price is more than `max_price_impact_bps`
+    /// below the pool spot price.
+    pub fn with_max_price_impact_bps(mut self, max_price_impact_bps: u64) -> Self {
+        self.max_price_impact_bps = Some(max_price_impact_bps);
+        self
+    }
+}
+
+impl SmartBuyInstruction {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_base_out: u64) -> Self {
+        SmartBuyInstruction {
+            input_amount,
+            slippage_bps,
+            min_base_out,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl SmartSellInstruction {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_quote_out: u64) -> Self {
+        SmartSellInstruction {
+            input_amount,
+            slippage_bps,
+            min_quote_out,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl CurveBuyInstruction {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_base_out: u64) -> Self {
+        CurveBuyInstruction {
+            input_amount,
+            slippage_bps,
+            min_base_out,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl CurveSellInstruction {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_quote_out: u64) -> Self {
+        CurveSellInstruction {
+            input_amount,
+            slippage_bps,
+            min_quote_out,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl RouteSwapInstruction {
+    pub fn new(input_amount: u64, min_output: u64, hops: Vec<Direction>) -> Self {
+        RouteSwapInstruction {
+            input_amount,
+            min_output,
+            hops,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl SplitBuyInstruction {
+    pub fn new(input_amount: u64, slippage_bps: u64, min_base_out: u64, pools: u8) -> Self {
+        SplitBuyInstruction {
+            input_amount,
+            slippage_bps,
+            min_base_out,
+            pools,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl CreateDcaInstruction {
+    pub fn new(
+        nonce: u64,
+        budget: u64,
+        amount_per_interval: u64,
+        interval_slots: u64,
+        slippage_bps: u64,
+        max_price: u128,
+    ) -> Self {
+        CreateDcaInstruction {
+            nonce,
+            budget,
+            amount_per_interval,
+            interval_slots,
+            slippage_bps,
+            max_price,
+        }
+    }
+}
+
+impl PlaceOrderInstruction {
+    pub fn new(
+        nonce: u64,
+        direction: Direction,
+        amount_in: u64,
+        limit_price: u128,
+        expires_at_slot: u64,
+        keeper_tip: u64,
+    ) -> Self {
+        PlaceOrderInstruction {
+            nonce,
+            direction,
+            amount_in,
+            limit_price,
+            expires_at_slot,
+            keeper_tip,
+        }
+    }
+}
+
+impl ArbitrageInstruction {
+    pub fn new(input_amount: u64, min_profit: u64, buy_venue: Venue, sell_venue: Venue) -> Self {
+        ArbitrageInstruction {
+            input_amount,
+            min_profit,
+            buy_venue,
+            sell_venue,
+            referrer: false,
+        }
+    }
+
+    /// Pays the referral share of the platform fee, see [`BuyInstruction::with_referrer`].
+    pub fn with_referrer(mut self) -> Self {
+        self.referrer = true;
+        self
+    }
+}
+
+impl InitializeInstruction {
+    pub fn new(platform_fee_bps: u64, fee_destination: Pubkey, referral_share_bps: u64) -> Self {
+        InitializeInstruction {
+            platform_fee_bps,
+            fee_destination,
+            referral_share_bps,
+        }
+    }
+}
+
+impl UpdateConfigInstruction {
+    pub fn new(platform_fee_bps: u64, fee_destination: Pubkey, referral_share_bps: u64) -> Self {
+        UpdateConfigInstruction {
+            platform_fee_bps,
+            fee_destination,
+            referral_share_bps,
+        }
+    }
+}
+
+impl SetPausedInstruction {
+    pub fn new(paused: bool) -> Self {
+        SetPausedInstruction { paused }
+    }
+}
+
+impl TransferAdminInstruction {
+    pub fn new(new_admin: Pubkey) -> Self {
+        TransferAdminInstruction { new_admin }
+    }
 }
 
 pub(crate) mod pumpfun_cpi {
@@ -40,6 +730,8 @@ pub(crate) mod pumpfun_cpi {
     use super::Error;
 
     const BUY_DISCRIMINATOR: &[u8] = &[102, 6, 61, 18, 1, 218, 235, 234];
+    const SELL_DISCRIMINATOR: &[u8] = &[51, 230, 133, 164, 1, 127, 131, 173];
+    const INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR: &[u8] = &[94, 6, 202, 115, 255, 96, 232, 183];
 
     #[derive(BorshSerialize, Debug)]
     pub struct Buy {
@@ -47,6 +739,13 @@ pub(crate) mod pumpfun_cpi {
         pub max_quote_amount_in: u64,
     }
 
+    #[derive(BorshSerialize, Debug)]
+    pub struct Sell {
+        pub base_amount_in: u64,
+        pub min_quote_amount_out: u64,
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn buy(
         pool: &Pubkey,
         user: &Pubkey,
@@ -107,279 +806,4080 @@ pub(crate) mod pumpfun_cpi {
 
         Ok(instruction)
     }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct PoolGlobalConfig {
-    pub admin: Pubkey,
-    pub lp_fee_basis_points: u64,
-    pub protocol_fee_basis_points: u64,
-    pub disable_flags: u8,
-    pub protocol_fee_recipients: [Pubkey; 8],
-    pub coin_creator_fee_basis_points: u64,
-    pub admin_set_coin_creator_authority: Pubkey,
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct Pool {
-    pub pool_bump: u8,
-    pub index: u16,
-    pub creator: Pubkey,
-    pub base_mint: Pubkey,
-    pub quote_mint: Pubkey,
-    pub lp_mint: Pubkey,
-    pub pool_base_token_account: Pubkey,
-    pub pool_quote_token_account: Pubkey,
-    pub lp_supply: u64,
-    pub coin_creator: Pubkey,
-}
 
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
-pub struct PoolData {
-    pub reserve_base: u64,
-    pub reserve_quote: u64,
-
-    pub global_config: PoolGlobalConfig,
-    pub pool: Pool,
-}
-
-impl PoolData {
-    fn new(
-        pool: &AccountInfo,
-        global_config: &AccountInfo,
-        pool_base_ata: &AccountInfo,
-        pool_quote_ata: &AccountInfo,
-    ) -> Result<PoolData, Error> {
-        if pool.data.as_ref().borrow().len() < 243 {
-            return Err(Error::ProgramError(ProgramError::InvalidArgument));
-        }
-        let pool = Pool::try_from_slice(&pool.data.as_ref().borrow()[8..243])
-            .map_err(Error::BorshIoError)?;
+    #[allow(clippy::too_many_arguments)]
+    pub fn sell(
+        pool: &Pubkey,
+        user: &Pubkey,
+        global_config: &Pubkey,
+        base: &Pubkey,
+        quote: &Pubkey,
+        user_base_ata: &Pubkey,
+        user_quote_ata: &Pubkey,
+        pool_base_ata: &Pubkey,
+        pool_quote_ata: &Pubkey,
+        protocol_fee_recipient: &Pubkey,
+        protocol_fee_recipient_ata: &Pubkey,
+        base_token_program: &Pubkey,
+        quote_token_program: &Pubkey,
+        system_program: &Pubkey,
+        associated_token_program: &Pubkey,
+        event_authority: &Pubkey,
+        pumpfun_program: &Pubkey,
+        coin_creator_vault_ata: &Pubkey,
+        coin_creator_vault_authority: &Pubkey,
+        instruction: Sell,
+    ) -> Result<Instruction, Error> {
+        let accounts = vec![
+            AccountMeta::new_readonly(*pool, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(*global_config, false),
+            AccountMeta::new_readonly(*base, false),
+            AccountMeta::new_readonly(*quote, false),
+            AccountMeta::new(*user_base_ata, false),
+            AccountMeta::new(*user_quote_ata, false),
+            AccountMeta::new(*pool_base_ata, false),
+            AccountMeta::new(*pool_quote_ata, false),
+            AccountMeta::new_readonly(*protocol_fee_recipient, false),
+            AccountMeta::new(*protocol_fee_recipient_ata, false),
+            AccountMeta::new_readonly(*base_token_program, false),
+            AccountMeta::new_readonly(*quote_token_program, false),
+            AccountMeta::new_readonly(*system_program, false),
+            AccountMeta::new_readonly(*associated_token_program, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*pumpfun_program, false),
+            AccountMeta::new(*coin_creator_vault_ata, false),
+            AccountMeta::new_readonly(*coin_creator_vault_authority, false),
+        ];
+
+        assert!(accounts.len() == 19);
+        let mut data = vec![];
+        data.extend(SELL_DISCRIMINATOR);
+        BorshSerialize::serialize(&instruction, &mut data).map_err(Error::BorshIoError)?;
+        let instruction = Instruction {
+            program_id: *pumpfun_program,
+            data,
+            accounts,
+        };
+
+        Ok(instruction)
+    }
+
+    pub fn init_user_volume_accumulator(
+        payer: &Pubkey,
+        user: &Pubkey,
+        user_volume_accumulator: &Pubkey,
+        system_program: &Pubkey,
+        event_authority: &Pubkey,
+        pumpfun_program: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: *pumpfun_program,
+            data: INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR.to_vec(),
+            accounts: vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(*user, false),
+                AccountMeta::new(*user_volume_accumulator, false),
+                AccountMeta::new_readonly(*system_program, false),
+                AccountMeta::new_readonly(*event_authority, false),
+                AccountMeta::new_readonly(*pumpfun_program, false),
+            ],
+        }
+    }
+}
+
+/// System, SPL Token and associated token program instructions the router invokes.
+pub(crate) mod token_cpi {
+    use solana_instruction::{AccountMeta, Instruction};
+    use solana_program::pubkey::Pubkey;
+
+    use super::{derive_associated_token_account, ASSOCIATED_TOKEN_PROGRAM, SYSTEM_PROGRAM};
+
+    const SYSTEM_CREATE_ACCOUNT: u32 = 0;
+    const SYSTEM_TRANSFER: u32 = 2;
+    const TOKEN_CLOSE_ACCOUNT: u8 = 9;
+    const TOKEN_TRANSFER_CHECKED: u8 = 12;
+    const TOKEN_SYNC_NATIVE: u8 = 17;
+    const ATA_CREATE_IDEMPOTENT: u8 = 1;
+
+    pub fn create_associated_token_account_idempotent(
+        payer: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Instruction {
+        let (ata, _) = derive_associated_token_account(owner, token_program, mint);
+        Instruction {
+            program_id: ASSOCIATED_TOKEN_PROGRAM,
+            accounts: vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new(ata, false),
+                AccountMeta::new_readonly(*owner, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM, false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+            data: vec![ATA_CREATE_IDEMPOTENT],
+        }
+    }
+
+    pub fn create_account(
+        from: &Pubkey,
+        to: &Pubkey,
+        lamports: u64,
+        space: u64,
+        owner: &Pubkey,
+    ) -> Instruction {
+        let mut data = Vec::from(SYSTEM_CREATE_ACCOUNT.to_le_bytes());
+        data.extend_from_slice(&lamports.to_le_bytes());
+        data.extend_from_slice(&space.to_le_bytes());
+        data.extend_from_slice(owner.as_ref());
+
+        Instruction {
+            program_id: SYSTEM_PROGRAM,
+            accounts: vec![AccountMeta::new(*from, true), AccountMeta::new(*to, true)],
+            data,
+        }
+    }
+
+    pub fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+        let mut data = Vec::from(SYSTEM_TRANSFER.to_le_bytes());
+        data.extend_from_slice(&lamports.to_le_bytes());
+
+        Instruction {
+            program_id: SYSTEM_PROGRAM,
+            accounts: vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+            data,
+        }
+    }
+
+    pub fn transfer_checked(
+        token_program: &Pubkey,
+        source: &Pubkey,
+        mint: &Pubkey,
+        destination: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        decimals: u8,
+    ) -> Instruction {
+        let mut data = vec![TOKEN_TRANSFER_CHECKED];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(decimals);
+
+        Instruction {
+            program_id: *token_program,
+            accounts: vec![
+                AccountMeta::new(*source, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(*owner, true),
+            ],
+            data,
+        }
+    }
+
+    pub fn sync_native(token_program: &Pubkey, account: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *token_program,
+            accounts: vec![AccountMeta::new(*account, false)],
+            data: vec![TOKEN_SYNC_NATIVE],
+        }
+    }
+
+    pub fn close_account(
+        token_program: &Pubkey,
+        account: &Pubkey,
+        destination: &Pubkey,
+        owner: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: *token_program,
+            accounts: vec![
+                AccountMeta::new(*account, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(*owner, true),
+            ],
+            data: vec![TOKEN_CLOSE_ACCOUNT],
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Typed view of [`PoolGlobalConfig::disable_flags`], a set bit disables the operation.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct DisableFlags: u8 {
+        const CREATE_POOL = 1 << 0;
+        const DEPOSIT = 1 << 1;
+        const WITHDRAW = 1 << 2;
+        const BUY = 1 << 3;
+        const SELL = 1 << 4;
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PoolGlobalConfig {
+    pub admin: Pubkey,
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    pub disable_flags: u8,
+    pub protocol_fee_recipients: [Pubkey; 8],
+    pub coin_creator_fee_basis_points: u64,
+    pub admin_set_coin_creator_authority: Pubkey,
+}
+
+impl PoolGlobalConfig {
+    pub fn disabled_operations(&self) -> DisableFlags {
+        DisableFlags::from_bits_retain(self.disable_flags)
+    }
+
+    /// Fails with [`Error::OperationDisabled`] if PumpSwap would reject `operation`.
+    pub fn check_enabled(&self, operation: DisableFlags) -> Result<(), Error> {
+        if self.disabled_operations().intersects(operation) {
+            return Err(Error::OperationDisabled(operation));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Pool {
+    pub pool_bump: u8,
+    pub index: u16,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub lp_supply: u64,
+    pub coin_creator: Pubkey,
+}
+
+pub fn derive_global_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global_config"], &PUMPFUN_AMM_PROGRAM)
+}
+
+pub fn derive_coin_creator_vault_authority(coin_creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"creator_vault", coin_creator.as_ref()],
+        &PUMPFUN_AMM_PROGRAM,
+    )
+}
+
+pub fn derive_associated_token_account(
+    owner: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM,
+    )
+}
+
+/// Accounts shared by the PumpSwap `buy` and `sell` instructions, in the order both of them
+/// expect.
+pub struct SwapAccounts<'a, 'info> {
+    pub(crate) pool: &'a AccountInfo<'info>,
+    pub(crate) user: &'a AccountInfo<'info>,
+    pub(crate) global_config: &'a AccountInfo<'info>,
+    pub(crate) base: &'a AccountInfo<'info>,
+    pub(crate) quote: &'a AccountInfo<'info>,
+    pub(crate) user_base_ata: &'a AccountInfo<'info>,
+    pub(crate) user_quote_ata: &'a AccountInfo<'info>,
+    pub(crate) pool_base_ata: &'a AccountInfo<'info>,
+    pub(crate) pool_quote_ata: &'a AccountInfo<'info>,
+    pub(crate) protocol_fee_recipient: &'a AccountInfo<'info>,
+    pub(crate) protocol_fee_recipient_ata: &'a AccountInfo<'info>,
+    pub(crate) base_token_program: &'a AccountInfo<'info>,
+    pub(crate) quote_token_program: &'a AccountInfo<'info>,
+    pub(crate) system_program: &'a AccountInfo<'info>,
+    pub(crate) associated_token_program: &'a AccountInfo<'info>,
+    pub(crate) event_authority: &'a AccountInfo<'info>,
+    pub(crate) pumpfun_program: &'a AccountInfo<'info>,
+    pub(crate) coin_creator_vault_ata: &'a AccountInfo<'info>,
+    pub(crate) coin_creator_vault_authority: &'a AccountInfo<'info>,
+    /// Seeds of the user when it is a router PDA, which signs through `invoke_signed`.
+    pub(crate) signer_seeds: Vec<Vec<u8>>,
+}
+
+impl<'a, 'info> SwapAccounts<'a, 'info> {
+    pub fn next(iter: &mut Iter<'a, AccountInfo<'info>>) -> Result<Self, Error> {
+        Ok(SwapAccounts {
+            pool: next_account_info(iter)?,
+            user: next_account_info(iter)?,
+            global_config: next_account_info(iter)?,
+            base: next_account_info(iter)?,
+            quote: next_account_info(iter)?,
+            user_base_ata: next_account_info(iter)?,
+            user_quote_ata: next_account_info(iter)?,
+            pool_base_ata: next_account_info(iter)?,
+            pool_quote_ata: next_account_info(iter)?,
+            protocol_fee_recipient: next_account_info(iter)?,
+            protocol_fee_recipient_ata: next_account_info(iter)?,
+            base_token_program: next_account_info(iter)?,
+            quote_token_program: next_account_info(iter)?,
+            system_program: next_account_info(iter)?,
+            associated_token_program: next_account_info(iter)?,
+            event_authority: next_account_info(iter)?,
+            pumpfun_program: next_account_info(iter)?,
+            coin_creator_vault_ata: next_account_info(iter)?,
+            coin_creator_vault_authority: next_account_info(iter)?,
+            signer_seeds: Vec::new(),
+        })
+    }
+
+    /// Pins every program the router passes to PumpSwap, the user signature is forwarded to
+    /// `pumpfun_program` so it must never be attacker supplied.
+    pub fn check_programs(&self) -> Result<(), Error> {
+        if *self.pumpfun_program.key != PUMPFUN_AMM_PROGRAM {
+            return Err(Error::InvalidPumpfunProgram);
+        }
+
+        for (mint, token_program) in [
+            (self.base, self.base_token_program),
+            (self.quote, self.quote_token_program),
+        ] {
+            if *token_program.key != SPL_TOKEN_PROGRAM
+                && *token_program.key != SPL_TOKEN_2022_PROGRAM
+            {
+                return Err(Error::InvalidTokenProgram);
+            }
+
+            // The mint is owned by the token program it was created with
+            if mint.owner != token_program.key {
+                return Err(Error::InvalidTokenProgram);
+            }
+        }
+
+        if *self.system_program.key != SYSTEM_PROGRAM {
+            return Err(Error::InvalidSystemProgram);
+        }
+
+        if *self.associated_token_program.key != ASSOCIATED_TOKEN_PROGRAM {
+            return Err(Error::InvalidAssociatedTokenProgram);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the mints, fee recipient and coin creator vault accounts against the decoded
+    /// pool state, so a mismatch fails here instead of deep inside PumpSwap.
+    pub fn check_pool_accounts(&self, pool_state: &PoolData) -> Result<(), Error> {
+        let pool = &pool_state.pool;
+        if *self.base.key != pool.base_mint || *self.quote.key != pool.quote_mint {
+            return Err(Error::MintMismatch);
+        }
+
+        let recipients = &pool_state.global_config.protocol_fee_recipients;
+        if *self.protocol_fee_recipient.key == Pubkey::default()
+            || !recipients.contains(self.protocol_fee_recipient.key)
+        {
+            return Err(Error::InvalidProtocolFeeRecipient);
+        }
+
+        let (protocol_fee_recipient_ata, _) = derive_associated_token_account(
+            self.protocol_fee_recipient.key,
+            self.quote_token_program.key,
+            &pool.quote_mint,
+        );
+        if *self.protocol_fee_recipient_ata.key != protocol_fee_recipient_ata {
+            return Err(Error::InvalidProtocolFeeRecipientAta);
+        }
+
+        let (coin_creator_vault_authority, _) =
+            derive_coin_creator_vault_authority(&pool.coin_creator);
+        if *self.coin_creator_vault_authority.key != coin_creator_vault_authority {
+            return Err(Error::InvalidCoinCreatorVaultAuthority);
+        }
+
+        let (coin_creator_vault_ata, _) = derive_associated_token_account(
+            &coin_creator_vault_authority,
+            self.quote_token_program.key,
+            &pool.quote_mint,
+        );
+        if *self.coin_creator_vault_ata.key != coin_creator_vault_ata {
+            return Err(Error::InvalidCoinCreatorVaultAta);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the user base token account is the associated token account of the user for
+    /// the token program owning the base mint, the account a buy creates.
+    pub fn check_user_base_ata(&self) -> Result<(), Error> {
+        let (base_ata, _) =
+            derive_associated_token_account(self.user.key, self.base.owner, self.base.key);
+        if *self.user_base_ata.key != base_ata {
+            return Err(Error::InvalidUserAta);
+        }
+
+        Ok(())
+    }
+
+    pub fn pool_data(&self) -> Result<PoolData, Error> {
+        PoolData::new(
+            self.pool,
+            self.global_config,
+            self.pool_base_ata,
+            self.pool_quote_ata,
+        )
+    }
+}
+
+/// Leading fields of an SPL Token / Token-2022 account, Token-2022 extensions are stored after
+/// the base 165 bytes layout so the offsets are the same for both programs.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+impl TokenAccount {
+    const LEN: usize = 165;
+
+    pub fn new(account: &AccountInfo) -> Result<TokenAccount, Error> {
+        if *account.owner != SPL_TOKEN_PROGRAM && *account.owner != SPL_TOKEN_2022_PROGRAM {
+            return Err(Error::InvalidTokenAccount);
+        }
+
+        if account.data.as_ref().borrow().len() < Self::LEN {
+            return Err(Error::InvalidTokenAccount);
+        }
+
+        TokenAccount::try_from_slice(&account.data.as_ref().borrow()[0..72])
+            .map_err(Error::BorshIoError)
+    }
+}
+
+/// Return data of every swap instruction, for programs calling the router through CPI.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapResult {
+    pub input_amount: u64,
+    pub output_amount: u64,
+    /// Pool reserves after the swap.
+    pub reserve_base: u64,
+    pub reserve_quote: u64,
+}
+
+impl SwapResult {
+    pub fn set_return_data(&self) -> Result<(), Error> {
+        let data = borsh::to_vec(self).map_err(Error::BorshIoError)?;
+        set_return_data(&data);
+
+        Ok(())
+    }
+}
+
+impl From<&SwapPerformed> for SwapResult {
+    fn from(event: &SwapPerformed) -> Self {
+        SwapResult {
+            input_amount: event.input_amount,
+            output_amount: event.output_amount,
+            reserve_base: event.reserve_base,
+            reserve_quote: event.reserve_quote,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuoteResult {
+    pub direction: Direction,
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub fees: Fees,
+    /// Quote per base before the swap, scaled by [`PRICE_SCALE`].
+    pub spot_price: u128,
+    /// Distance between the effective execution price, fees included, and the spot price.
+    pub price_impact_bps: u64,
+}
+
+impl QuoteResult {
+    pub fn set_return_data(&self) -> Result<(), Error> {
+        let data = borsh::to_vec(self).map_err(Error::BorshIoError)?;
+        set_return_data(&data);
+
+        Ok(())
+    }
+}
+
+/// Fixed point scale of prices, expressed in quote atoms per base atom.
+pub const PRICE_SCALE: u128 = 1_000_000_000_000_000_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fees {
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub coin_creator_fee: u64,
+}
+
+impl Fees {
+    pub fn total(&self) -> Result<u64, ProgramError> {
+        self.lp_fee
+            .checked_add(self.protocol_fee)
+            .and_then(|x| x.checked_add(self.coin_creator_fee))
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct PoolData {
+    pub reserve_base: u64,
+    pub reserve_quote: u64,
+
+    pub global_config: PoolGlobalConfig,
+    pub pool: Pool,
+    /// Router platform fee included in [`PoolData::base_out`] and [`PoolData::quote_out`],
+    /// see [`PoolData::with_platform_fee_bps`].
+    pub platform_fee_bps: u64,
+}
+
+/// Platform fee of `platform_fee_bps` on `quote_amount`, rounded up.
+pub(crate) fn platform_fee(quote_amount: u64, platform_fee_bps: u64) -> Result<u64, ProgramError> {
+    let fee = ceil_div(
+        (quote_amount as u128)
+            .checked_mul(platform_fee_bps as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        10_000,
+    )?;
+
+    u64::try_from(fee).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+pub(crate) fn ceil_div(num: u128, denom: u128) -> Result<u128, ProgramError> {
+    if denom == 0 {
+        return Err(ProgramError::ArithmeticOverflow);
+    }
+
+    Ok(num.div_ceil(denom))
+}
+
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton's method from a power of two above the root
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+
+        x = y;
+    }
+}
+
+/// Splits `quote_amount_in` across pools of the same pair so that the total base out is
+/// maximal, returns the quote for every pool in order.
+///
+/// Fees are carved out of the input before it reaches the curve, so a pool charging the fee
+/// rate `f` pays out for the quote `x` like a fee free pool holding `q = reserve_quote * (1 +
+/// f)`, at the marginal rate `reserve_base * q / (q + x)^2`. Equal rates across pools give
+/// `x_i = sqrt(reserve_base_i * q_i) * (X + sum(q)) / sum(sqrt(..)) - q_i`, pools whose `x_i`
+/// would not be positive are left out.
+pub fn split_buy(pools: &[PoolData], quote_amount_in: u64) -> Result<Vec<u64>, Error> {
+    if pools.is_empty() {
+        return Err(ProgramError::InvalidInstructionData.into());
+    }
+
+    let total = quote_amount_in as u128;
+    let reserves = pools
+        .iter()
+        .map(|pool| {
+            let total_fee_bp = pool.total_fee_basis_points()?;
+            (pool.reserve_quote as u128)
+                .checked_mul(10000u128 + total_fee_bp as u128)
+                .map(|x| x / 10000u128)
+                .ok_or(ProgramError::ArithmeticOverflow)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let roots = pools
+        .iter()
+        .zip(&reserves)
+        .map(|(pool, reserve)| {
+            (pool.reserve_base as u128)
+                .checked_mul(*reserve)
+                .map(isqrt)
+                .ok_or(ProgramError::ArithmeticOverflow)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut active = roots.iter().map(|root| *root > 0).collect::<Vec<_>>();
+    let mut amounts = vec![0u128; pools.len()];
+
+    loop {
+        let (sum_roots, sum_reserves) = reserves
+            .iter()
+            .zip(&roots)
+            .zip(&active)
+            .filter(|(_, active)| **active)
+            .fold((0u128, 0u128), |(roots, sum), ((reserve, root), _)| {
+                (roots + root, sum + reserve)
+            });
+
+        if sum_roots == 0 {
+            return Err(Error::InsufficientPoolReserve);
+        }
+
+        let scaled = total
+            .checked_add(sum_reserves)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let mut dropped = false;
+        for (i, reserve) in reserves.iter().enumerate() {
+            if !active[i] {
+                amounts[i] = 0;
+                continue;
+            }
+
+            let target = roots[i]
+                .checked_mul(scaled)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                / sum_roots;
+
+            match target.checked_sub(*reserve) {
+                Some(x) if x > 0 => amounts[i] = x,
+                _ => {
+                    active[i] = false;
+                    dropped = true;
+                }
+            }
+        }
+
+        if !dropped {
+            break;
+        }
+    }
+
+    // Rounding leftovers go to the largest allocation
+    let sum_amounts = amounts.iter().sum::<u128>();
+    let mut allocations = amounts
+        .iter()
+        .map(|x| (quote_amount_in as u128 * x / sum_amounts) as u64)
+        .collect::<Vec<_>>();
+
+    let allocated = allocations.iter().sum::<u64>();
+    if let Some(largest) = allocations.iter_mut().max() {
+        *largest += quote_amount_in - allocated;
+    }
+
+    Ok(allocations)
+}
+
+/// `quote / base` scaled by [`PRICE_SCALE`], an empty side yields the maximum price.
+pub(crate) fn price(quote: u64, base: u64) -> Result<u128, ProgramError> {
+    if base == 0 {
+        return Ok(u128::MAX);
+    }
+
+    (quote as u128)
+        .checked_mul(PRICE_SCALE)
+        .map(|x| x / base as u128)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+impl Pool {
+    /// Reads a PumpSwap pool account, checking its owner, type and address.
+    pub fn load(pool_account: &AccountInfo) -> Result<Pool, Error> {
+        PoolData::check_account(pool_account, PoolData::POOL_DISCRIMINATOR, 243)?;
+        let pool = Pool::try_from_slice(&pool_account.data.as_ref().borrow()[8..243])
+            .map_err(Error::BorshIoError)?;
+
+        // The bump is stored by PumpSwap on pool creation, so there is no need to search for it
+        let expected_pool = Pubkey::create_program_address(
+            &[
+                b"pool",
+                &pool.index.to_be_bytes(),
+                pool.creator.as_ref(),
+                pool.base_mint.as_ref(),
+                pool.quote_mint.as_ref(),
+                &[pool.pool_bump],
+            ],
+            &PUMPFUN_AMM_PROGRAM,
+        )
+        .map_err(|_| Error::InvalidPoolAddress)?;
+
+        if *pool_account.key != expected_pool {
+            return Err(Error::InvalidPoolAddress);
+        }
+
+        Ok(pool)
+    }
+}
+
+impl PoolData {
+    pub const POOL_DISCRIMINATOR: &[u8] = &[241, 154, 109, 4, 17, 177, 109, 188];
+    pub const GLOBAL_CONFIG_DISCRIMINATOR: &[u8] = &[149, 8, 156, 202, 160, 252, 176, 217];
+
+    /// Reads the pool state with the reserves held by the pool token accounts, checking every
+    /// account against the pool.
+    pub fn new(
+        pool_account: &AccountInfo,
+        global_config_account: &AccountInfo,
+        pool_base_ata: &AccountInfo,
+        pool_quote_ata: &AccountInfo,
+    ) -> Result<PoolData, Error> {
+        let pool = Pool::load(pool_account)?;
+
+        Self::check_account(
+            global_config_account,
+            Self::GLOBAL_CONFIG_DISCRIMINATOR,
+            353,
+        )?;
+
+        let (expected_global_config, _) = derive_global_config();
+        if *global_config_account.key != expected_global_config {
+            return Err(Error::InvalidGlobalConfigAddress);
+        }
+
+        let global_config =
+            PoolGlobalConfig::try_from_slice(&global_config_account.data.as_ref().borrow()[8..353])
+                .map_err(Error::BorshIoError)?;
+
+        if *pool_base_ata.key != pool.pool_base_token_account
+            || *pool_quote_ata.key != pool.pool_quote_token_account
+        {
+            return Err(Error::PoolTokenAccountMismatch);
+        }
+
+        let base = TokenAccount::new(pool_base_ata)?;
+        let quote = TokenAccount::new(pool_quote_ata)?;
+        if base.mint != pool.base_mint || quote.mint != pool.quote_mint {
+            return Err(Error::MintMismatch);
+        }
+
+        Ok(PoolData {
+            global_config,
+            pool,
+            reserve_base: base.amount,
+            reserve_quote: quote.amount,
+            platform_fee_bps: 0,
+        })
+    }
+
+    /// Quotes as the router executes a swap taking a platform fee of `platform_fee_bps`.
+    pub fn with_platform_fee_bps(mut self, platform_fee_bps: u64) -> Self {
+        self.platform_fee_bps = platform_fee_bps;
+        self
+    }
+
+    /// Platform fee on `quote_amount`, the quote input of a buy or the quote output of a sell.
+    pub fn platform_fee(&self, quote_amount: u64) -> Result<u64, ProgramError> {
+        platform_fee(quote_amount, self.platform_fee_bps)
+    }
+
+    /// Largest quote PumpSwap may charge on a buy so that, with the platform fee on top of it,
+    /// the user spends at most `max_quote_in`.
+    pub fn max_quote_before_platform_fee(&self, max_quote_in: u64) -> Result<u64, ProgramError> {
+        let mut quote = (max_quote_in as u128)
+            .checked_mul(10_000)
+            .map(|x| x / (10_000 + self.platform_fee_bps as u128))
+            .and_then(|x| u64::try_from(x).ok())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // The fee rounds up, which may take the total a few atoms past the maximum
+        while quote > 0 && quote + self.platform_fee(quote)? > max_quote_in {
+            quote -= 1;
+        }
+
+        Ok(quote)
+    }
+
+    /// Checks that `account` is a PumpSwap Anchor account of the type identified by
+    /// `discriminator` and holds at least `len` bytes.
+    fn check_account(account: &AccountInfo, discriminator: &[u8], len: usize) -> Result<(), Error> {
+        if *account.owner != PUMPFUN_AMM_PROGRAM {
+            return Err(Error::InvalidAccountOwner);
+        }
+
+        let data = account.data.as_ref().borrow();
+        if data.len() < len {
+            return Err(Error::ProgramError(ProgramError::InvalidArgument));
+        }
+
+        if &data[..8] != discriminator {
+            return Err(Error::InvalidAccountDiscriminator);
+        }
+
+        Ok(())
+    }
+
+    /// Base received for spending `quote_amount_in`, fees included. As in PumpSwap, the
+    /// fees are carved out of the input before it reaches the curve.
+    pub fn base_out(&self, quote_amount_in: u64) -> Result<u64, ProgramError> {
+        // The platform fee is skimmed before the quote reaches PumpSwap
+        let quote_amount_in = quote_amount_in
+            .checked_sub(self.platform_fee(quote_amount_in)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // Effective quote in amount
+        //
+        // quote = quote_amount_in * 10000 / (10000 + total_fee_bp)
+        let total_fee_bp = self.total_fee_basis_points()?;
+        let quote = (quote_amount_in as u128)
+            .checked_mul(10000u128)
+            .and_then(|x| x.checked_div(10000u128 + total_fee_bp as u128))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let mut quote = quote;
+        loop {
+            // 1) Find pool reserve relation accounting for `quote_amount_in`
+            // 2) Multiply on quote
+            //
+            // base_out = (reserve_base * quote) / (reserve_quote + quote)
+            let num = (self.reserve_base as u128)
+                .checked_mul(quote)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            let denom = (self.reserve_quote as u128)
+                .checked_add(quote)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            let base_out = num
+                .checked_div(denom)
+                .ok_or(ProgramError::ArithmeticOverflow)? as u64;
+
+            if base_out == 0 {
+                return Ok(0);
+            }
+
+            // PumpSwap prices the buy by its base out and rounds the cost up, which can
+            // exceed `quote_amount_in` by a few atoms. Shrink the effective quote by the
+            // excess until the buy fits.
+            let (cost, _) = self.buy_cost(base_out)?;
+            match cost.checked_sub(quote_amount_in) {
+                Some(excess) if excess > 0 => quote = quote.saturating_sub(excess as u128),
+                _ => return Ok(base_out),
+            }
+        }
+    }
+
+    pub fn quote_out(&self, base_amount_in: u64) -> Result<u64, ProgramError> {
+        let quote_out = self.quote_out_before_platform_fee(base_amount_in)?;
+
+        // The platform fee is skimmed from the quote PumpSwap pays out
+        quote_out
+            .checked_sub(self.platform_fee(quote_out)?)
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+
+    /// Quote PumpSwap pays out for `base_amount_in`, before the router platform fee.
+    pub fn quote_out_before_platform_fee(&self, base_amount_in: u64) -> Result<u64, ProgramError> {
+        let quote_out = self.quote_out_before_fees(base_amount_in)?;
+
+        // Sell fees are charged on the quote leaving the pool
+        let fees = self.fees(quote_out)?;
+        quote_out
+            .checked_sub(fees.total()?)
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+
+    /// Quote needed to buy exactly `base_amount_out`, fees included, with the fee breakdown.
+    /// This is what PumpSwap `buy` charges.
+    pub fn quote_in(&self, base_amount_out: u64) -> Result<(u64, Fees), Error> {
+        if base_amount_out >= self.reserve_base {
+            return Err(Error::InsufficientPoolReserve);
+        }
+
+        Ok(self.buy_cost(base_amount_out)?)
+    }
+
+    fn buy_cost(&self, base_amount_out: u64) -> Result<(u64, Fees), ProgramError> {
+        // quote_in = ceil((reserve_quote * base) / (reserve_base - base))
+        let num = (self.reserve_quote as u128)
+            .checked_mul(base_amount_out as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let denom = self
+            .reserve_base
+            .checked_sub(base_amount_out)
+            .ok_or(ProgramError::ArithmeticOverflow)? as u128;
+        let quote_in =
+            u64::try_from(ceil_div(num, denom)?).map_err(|_| ProgramError::ArithmeticOverflow)?;
+
+        let fees = self.fees(quote_in)?;
+        let total = quote_in
+            .checked_add(fees.total()?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        Ok((total, fees))
+    }
+
+    /// Quote released by the curve for `base_amount_in`, the amount sell fees are charged on.
+    pub fn quote_out_before_fees(&self, base_amount_in: u64) -> Result<u64, ProgramError> {
+        // quote_out = (reserve_quote * base) / (reserve_base + base)
+        let num = (self.reserve_quote as u128)
+            .checked_mul(base_amount_in as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let denom = (self.reserve_base as u128)
+            .checked_add(base_amount_in as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let quote_out = num
+            .checked_div(denom)
+            .ok_or(ProgramError::ArithmeticOverflow)? as u64;
+
+        Ok(quote_out)
+    }
+
+    /// Expected output, fees and price impact of swapping `amount` in `direction`.
+    pub fn quote(&self, direction: Direction, amount: u64) -> Result<QuoteResult, Error> {
+        let (output_amount, fees) = match direction {
+            Direction::Buy => {
+                // PumpSwap charges the buy by its base amount out
+                let base_out = self.base_out(amount)?;
+                (base_out, self.quote_in(base_out)?.1)
+            }
+            Direction::Sell => (
+                self.quote_out(amount)?,
+                self.fees(self.quote_out_before_fees(amount)?)?,
+            ),
+        };
+
+        Ok(QuoteResult {
+            direction,
+            input_amount: amount,
+            output_amount,
+            fees,
+            spot_price: self.spot_price()?,
+            price_impact_bps: self.price_impact_bps(direction, amount, output_amount)?,
+        })
+    }
+
+    /// Compares the quoted execution price with the spot price, slippage is relative to the
+    /// quote and does not catch an order that moves a thin pool by itself.
+    pub fn check_price_impact(
+        &self,
+        direction: Direction,
+        input_amount: u64,
+        output_amount: u64,
+        max_price_impact_bps: Option<u64>,
+    ) -> Result<(), Error> {
+        let Some(max_price_impact_bps) = max_price_impact_bps else {
+            return Ok(());
+        };
+
+        let impact = self.price_impact_bps(direction, input_amount, output_amount)?;
+        if impact > max_price_impact_bps {
+            solana_program::msg!(
+                "Price impact {} bps exceeds {} bps",
+                impact,
+                max_price_impact_bps
+            );
+            return Err(Error::PriceImpactTooHigh);
+        }
+
+        Ok(())
+    }
+
+    /// Fails with [`Error::ReservesDrifted`] when either reserve is further from the expected
+    /// one than the tolerance allows.
+    pub fn check_reserves(&self, expected: &ExpectedReserves) -> Result<(), Error> {
+        let within = |actual: u64, expected_reserve: u64| -> Result<bool, ProgramError> {
+            let drift = actual.abs_diff(expected_reserve) as u128;
+            let allowed = (expected_reserve as u128)
+                .checked_mul(expected.tolerance_bps as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok(drift * 10_000 <= allowed)
+        };
+
+        if !within(self.reserve_base, expected.reserve_base)?
+            || !within(self.reserve_quote, expected.reserve_quote)?
+        {
+            return Err(Error::ReservesDrifted);
+        }
+
+        Ok(())
+    }
+
+    /// Quote per base implied by the reserves, scaled by [`PRICE_SCALE`].
+    pub fn spot_price(&self) -> Result<u128, ProgramError> {
+        price(self.reserve_quote, self.reserve_base)
+    }
+
+    /// How much worse than the spot price a swap of `input_amount` for `output_amount`
+    /// executes, in basis points.
+    pub fn price_impact_bps(
+        &self,
+        direction: Direction,
+        input_amount: u64,
+        output_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let spot = self.spot_price()?;
+        let execution = match direction {
+            Direction::Buy => price(input_amount, output_amount)?,
+            Direction::Sell => price(output_amount, input_amount)?,
+        };
+
+        // Buyers pay above the spot price, sellers receive below it
+        let diff = match direction {
+            Direction::Buy => execution.saturating_sub(spot),
+            Direction::Sell => spot.saturating_sub(execution),
+        };
+
+        let impact = diff
+            .checked_mul(10_000)
+            .and_then(|x| x.checked_div(spot))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        Ok(impact.min(u64::MAX as u128) as u64)
+    }
+
+    /// Breakdown of the fees charged on a quote `amount`. PumpSwap rounds every component
+    /// up on its own.
+    pub fn fees(&self, amount: u64) -> Result<Fees, ProgramError> {
+        let fee = |bps: u64| -> Result<u64, ProgramError> {
+            let num = (amount as u128)
+                .checked_mul(bps as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok(ceil_div(num, 10000u128)? as u64)
+        };
+
+        Ok(Fees {
+            lp_fee: fee(self.global_config.lp_fee_basis_points)?,
+            protocol_fee: fee(self.global_config.protocol_fee_basis_points)?,
+            coin_creator_fee: fee(self.coin_creator_fee_basis_points())?,
+        })
+    }
+
+    /// Pools without a coin creator are not charged the creator fee.
+    fn coin_creator_fee_basis_points(&self) -> u64 {
+        if self.pool.coin_creator == Pubkey::default() {
+            return 0;
+        }
+
+        self.global_config.coin_creator_fee_basis_points
+    }
+
+    fn total_fee_basis_points(&self) -> Result<u64, ProgramError> {
+        self.global_config
+            .lp_fee_basis_points
+            .checked_add(self.global_config.protocol_fee_basis_points)
+            .and_then(|x| x.checked_add(self.coin_creator_fee_basis_points()))
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+
+    #[error(transparent)]
+    BorshIoError(borsh::io::Error),
+
+    #[error("insufficient pool reserve")]
+    InsufficientPoolReserve,
+
+    #[error("slippage must be within 0 to 100 percent in basis points")]
+    SlippageTooHigh,
+
+    #[error("account is not an SPL Token or Token-2022 account")]
+    InvalidTokenAccount,
+
+    #[error("pool token account does not match the one recorded in the pool")]
+    PoolTokenAccountMismatch,
+
+    #[error("token account mint does not match the pool mint")]
+    MintMismatch,
+
+    #[error("account is not owned by the PumpSwap program")]
+    InvalidAccountOwner,
+
+    #[error("account discriminator does not match the expected PumpSwap account")]
+    InvalidAccountDiscriminator,
+
+    #[error("pool address does not match the pool PDA")]
+    InvalidPoolAddress,
+
+    #[error("global config address does not match the global config PDA")]
+    InvalidGlobalConfigAddress,
+
+    #[error("program account is not the PumpSwap program")]
+    InvalidPumpfunProgram,
+
+    #[error("token program is not SPL Token or Token-2022, or does not own the mint")]
+    InvalidTokenProgram,
+
+    #[error("program account is not the system program")]
+    InvalidSystemProgram,
+
+    #[error("program account is not the associated token program")]
+    InvalidAssociatedTokenProgram,
+
+    #[error("protocol fee recipient is not one of the global config recipients")]
+    InvalidProtocolFeeRecipient,
+
+    #[error("protocol fee recipient token account is not its quote mint ATA")]
+    InvalidProtocolFeeRecipientAta,
+
+    #[error("coin creator vault authority is not derived from the pool coin creator")]
+    InvalidCoinCreatorVaultAuthority,
+
+    #[error("coin creator vault token account is not the vault authority quote mint ATA")]
+    InvalidCoinCreatorVaultAta,
+
+    #[error("received amount is below the minimum out")]
+    InsufficientOutput,
+
+    #[error("operation is disabled in the PumpSwap global config: {0:?}")]
+    OperationDisabled(DisableFlags),
+
+    #[error("native SOL swaps require a WSOL quote mint")]
+    NativeSolUnsupported,
+
+    #[error("native SOL swaps require an empty user WSOL account")]
+    WsolAccountNotEmpty,
+
+    #[error("user token account is not the associated token account")]
+    InvalidUserAta,
+
+    #[error("launchpad program does not match the Pumpfun launchpad")]
+    InvalidLaunchpadProgram,
+
+    #[error("bonding curve address does not match the mint")]
+    InvalidBondingCurveAddress,
+
+    #[error("launchpad global address does not match the canonical PDA")]
+    InvalidLaunchpadGlobalAddress,
+
+    #[error("fee recipient is not configured in the launchpad global")]
+    InvalidLaunchpadFeeRecipient,
+
+    #[error("bonding curve token account is not the associated token account")]
+    InvalidAssociatedBondingCurve,
+
+    #[error("creator vault does not match the bonding curve creator")]
+    InvalidCreatorVault,
+
+    #[error("bonding curve is complete, the token trades on PumpSwap")]
+    CurveComplete,
+
+    #[error("route hop does not spend the output of the previous hop")]
+    RouteMismatch,
+
+    #[error("split legs must be distinct pools of the same pair")]
+    InvalidSplit,
+
+    #[error("arbitrage venues must trade the same pair for the same user")]
+    InvalidArbitrage,
+
+    #[error("arbitrage profit is below the minimum")]
+    UnprofitableArbitrage,
+
+    #[error("swap landed after its valid until slot")]
+    SwapExpired,
+
+    #[error("pool reserves drifted beyond the tolerance")]
+    ReservesDrifted,
+
+    #[error("price impact exceeds the maximum")]
+    PriceImpactTooHigh,
+
+    #[error("router config address does not match its seeds")]
+    InvalidRouterConfigAddress,
+
+    #[error("signer is not the router admin")]
+    InvalidRouterAdmin,
+
+    #[error("signer is not the pending router admin")]
+    InvalidPendingAdmin,
+
+    #[error("router program data address does not match its seeds")]
+    InvalidProgramDataAddress,
+
+    #[error("signer is not the router upgrade authority")]
+    InvalidUpgradeAuthority,
+
+    #[error("platform fee must be below 100 percent in basis points")]
+    InvalidPlatformFee,
+
+    #[error("router is paused")]
+    RouterPaused,
+
+    #[error("referral share must be within 0 to 100 percent in basis points")]
+    InvalidReferralShare,
+
+    #[error("referral address does not match its seeds")]
+    InvalidReferralAddress,
+
+    #[error("referrer token account does not match the referral")]
+    InvalidReferral,
+
+    #[error("referrer is the trader")]
+    SelfReferral,
+
+    #[error("fee vault is not the fee destination token account")]
+    InvalidFeeVault,
+
+    #[error("order address does not match its seeds")]
+    InvalidOrderAddress,
+
+    #[error("order authority does not match the order")]
+    InvalidOrderAuthority,
+
+    #[error("account is not the order owner")]
+    InvalidOrderOwner,
+
+    #[error("swap accounts are not for the order pool")]
+    InvalidOrderPool,
+
+    #[error("limit price must be above zero")]
+    InvalidLimitPrice,
+
+    #[error("order expired")]
+    OrderExpired,
+
+    #[error("pool price has not crossed the order limit")]
+    OrderNotCrossed,
+
+    #[error("DCA address does not match its seeds")]
+    InvalidDcaAddress,
+
+    #[error("DCA authority does not match the DCA")]
+    InvalidDcaAuthority,
+
+    #[error("account is not the DCA owner")]
+    InvalidDcaOwner,
+
+    #[error("swap accounts are not for the DCA pool")]
+    InvalidDcaPool,
+
+    #[error("DCA amount per interval and interval must be above zero")]
+    InvalidDcaSchedule,
+
+    #[error("DCA interval has not elapsed")]
+    DcaNotDue,
+
+    #[error("DCA budget is spent")]
+    DcaCompleted,
+}
+
+impl From<Error> for ProgramError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::ProgramError(err) => err,
+            Error::BorshIoError(err) => ProgramError::BorshIoError(err.to_string()),
+            Error::InsufficientPoolReserve => ProgramError::InvalidInstructionData,
+            Error::SlippageTooHigh => ProgramError::InvalidArgument,
+            Error::InvalidTokenAccount => ProgramError::InvalidAccountData,
+            Error::PoolTokenAccountMismatch => ProgramError::InvalidArgument,
+            Error::MintMismatch => ProgramError::InvalidAccountData,
+            Error::InvalidAccountOwner => ProgramError::IllegalOwner,
+            Error::InvalidAccountDiscriminator => ProgramError::InvalidAccountData,
+            Error::InvalidPoolAddress => ProgramError::InvalidSeeds,
+            Error::InvalidGlobalConfigAddress => ProgramError::InvalidSeeds,
+            Error::InvalidPumpfunProgram => ProgramError::IncorrectProgramId,
+            Error::InvalidTokenProgram => ProgramError::IncorrectProgramId,
+            Error::InvalidSystemProgram => ProgramError::IncorrectProgramId,
+            Error::InvalidAssociatedTokenProgram => ProgramError::IncorrectProgramId,
+            Error::InvalidProtocolFeeRecipient => ProgramError::InvalidArgument,
+            Error::InvalidProtocolFeeRecipientAta => ProgramError::InvalidArgument,
+            Error::InvalidCoinCreatorVaultAuthority => ProgramError::InvalidSeeds,
+            Error::InvalidCoinCreatorVaultAta => ProgramError::InvalidArgument,
+            Error::InsufficientOutput => ProgramError::InvalidArgument,
+            Error::OperationDisabled(_) => ProgramError::InvalidArgument,
+            Error::NativeSolUnsupported => ProgramError::InvalidArgument,
+            Error::WsolAccountNotEmpty => ProgramError::InvalidArgument,
+            Error::InvalidUserAta => ProgramError::InvalidSeeds,
+            Error::InvalidLaunchpadProgram => ProgramError::IncorrectProgramId,
+            Error::InvalidBondingCurveAddress => ProgramError::InvalidSeeds,
+            Error::InvalidLaunchpadGlobalAddress => ProgramError::InvalidSeeds,
+            Error::InvalidLaunchpadFeeRecipient => ProgramError::InvalidArgument,
+            Error::InvalidAssociatedBondingCurve => ProgramError::InvalidArgument,
+            Error::InvalidCreatorVault => ProgramError::InvalidSeeds,
+            Error::CurveComplete => ProgramError::InvalidAccountData,
+            Error::RouteMismatch => ProgramError::InvalidArgument,
+            Error::InvalidSplit => ProgramError::InvalidArgument,
+            Error::InvalidArbitrage => ProgramError::InvalidArgument,
+            Error::UnprofitableArbitrage => ProgramError::InsufficientFunds,
+            Error::SwapExpired => ProgramError::InvalidArgument,
+            Error::ReservesDrifted => ProgramError::InvalidAccountData,
+            Error::PriceImpactTooHigh => ProgramError::InvalidArgument,
+            Error::InvalidRouterConfigAddress => ProgramError::InvalidSeeds,
+            Error::InvalidRouterAdmin => ProgramError::InvalidArgument,
+            Error::InvalidPendingAdmin => ProgramError::InvalidArgument,
+            Error::InvalidProgramDataAddress => ProgramError::InvalidSeeds,
+            Error::InvalidUpgradeAuthority => ProgramError::InvalidArgument,
+            Error::InvalidPlatformFee => ProgramError::InvalidArgument,
+            Error::RouterPaused => ProgramError::InvalidAccountData,
+            Error::InvalidReferralShare => ProgramError::InvalidArgument,
+            Error::InvalidReferralAddress => ProgramError::InvalidSeeds,
+            Error::InvalidReferral => ProgramError::InvalidArgument,
+            Error::SelfReferral => ProgramError::InvalidArgument,
+            Error::InvalidFeeVault => ProgramError::InvalidArgument,
+            Error::InvalidOrderAddress => ProgramError::InvalidSeeds,
+            Error::InvalidOrderAuthority => ProgramError::InvalidSeeds,
+            Error::InvalidOrderOwner => ProgramError::InvalidArgument,
+            Error::InvalidOrderPool => ProgramError::InvalidArgument,
+            Error::InvalidLimitPrice => ProgramError::InvalidArgument,
+            Error::OrderExpired => ProgramError::InvalidArgument,
+            Error::OrderNotCrossed => ProgramError::InvalidArgument,
+            Error::InvalidDcaAddress => ProgramError::InvalidSeeds,
+            Error::InvalidDcaAuthority => ProgramError::InvalidSeeds,
+            Error::InvalidDcaOwner => ProgramError::InvalidArgument,
+            Error::InvalidDcaPool => ProgramError::InvalidArgument,
+            Error::InvalidDcaSchedule => ProgramError::InvalidArgument,
+            Error::DcaNotDue => ProgramError::InvalidArgument,
+            Error::DcaCompleted => ProgramError::InsufficientFunds,
+        }
+    }
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub mod entrypoint {
+    use std::slice::Iter;
+
+    use borsh::BorshDeserialize;
+    use solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        instruction::Instruction,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        rent::Rent,
+        sysvar::Sysvar,
+    };
+
+    use crate::{
+        config::{
+            check_fees, derive_config, derive_referral, upgrade_authority, Referral, RouterConfig,
+        },
+        dca::{derive_dca, derive_dca_authority, Dca},
+        derive_associated_token_account,
+        launchpad::{self, derive_creator_vault, CurveData},
+        order::{derive_order, derive_order_authority, Order},
+        platform_fee, pumpfun_cpi, split_buy, token_cpi, ArbitrageInstruction,
+        BuyExactOutInstruction, BuyInstruction, CreateDcaInstruction, CurveBuyInstruction,
+        CurveSellInstruction, Direction, DisableFlags, Error, Fees, InitializeInstruction,
+        Instructions, PlaceOrderInstruction, Pool, PoolData, QuoteInstruction,
+        RouteSwapInstruction, SellInstruction, SmartBuyInstruction, SmartSellInstruction,
+        SplitBuyInstruction, SwapAccounts, SwapPerformed, SwapResult, TokenAccount, Venue,
+        ASSOCIATED_TOKEN_PROGRAM, NATIVE_MINT, PUMPFUN_AMM_PROGRAM, PUMPFUN_LAUNCHPAD_PROGRAM,
+        SPL_TOKEN_2022_PROGRAM, SPL_TOKEN_PROGRAM, SYSTEM_PROGRAM,
+    };
+
+    solana_program::entrypoint!(process_instruction);
+
+    pub fn process_instruction(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        let instruction = Instructions::try_from_slice(instruction_data)?;
+        let result = match instruction {
+            Instructions::ExecuteSwap(instruction) => {
+                msg!("Instruction: ExecuteSwap");
+                check_config(accounts)
+                    .and_then(|(config, accounts)| execute_swap(accounts, &config, instruction))
+            }
+            Instructions::ExecuteSell(instruction) => {
+                msg!("Instruction: ExecuteSell");
+                check_config(accounts)
+                    .and_then(|(config, accounts)| execute_sell(accounts, &config, instruction))
+            }
+            Instructions::Quote(instruction) => {
+                msg!("Instruction: Quote");
+                quote(accounts, instruction)
+            }
+            Instructions::BuyExactOut(instruction) => {
+                msg!("Instruction: BuyExactOut");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_buy_exact_out(accounts, &config, instruction)
+                })
+            }
+            Instructions::SmartBuy(instruction) => {
+                msg!("Instruction: SmartBuy");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_smart_buy(accounts, &config, instruction)
+                })
+            }
+            Instructions::SmartSell(instruction) => {
+                msg!("Instruction: SmartSell");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_smart_sell(accounts, &config, instruction)
+                })
+            }
+            Instructions::CurveBuy(instruction) => {
+                msg!("Instruction: CurveBuy");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_curve_buy(accounts, &config, instruction)
+                })
+            }
+            Instructions::CurveSell(instruction) => {
+                msg!("Instruction: CurveSell");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_curve_sell(accounts, &config, instruction)
+                })
+            }
+            Instructions::RouteSwap(instruction) => {
+                msg!("Instruction: RouteSwap");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_route_swap(accounts, &config, instruction)
+                })
+            }
+            Instructions::SplitBuy(instruction) => {
+                msg!("Instruction: SplitBuy");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_split_buy(accounts, &config, instruction)
+                })
+            }
+            Instructions::Arbitrage(instruction) => {
+                msg!("Instruction: Arbitrage");
+                check_config(accounts).and_then(|(config, accounts)| {
+                    execute_arbitrage(accounts, &config, instruction)
+                })
+            }
+            Instructions::Initialize(instruction) => {
+                msg!("Instruction: Initialize");
+                initialize(accounts, instruction)
+            }
+            Instructions::UpdateConfig(instruction) => {
+                msg!("Instruction: UpdateConfig");
+                update_config(accounts, |config, admin| {
+                    config.update(
+                        admin,
+                        instruction.platform_fee_bps,
+                        instruction.fee_destination,
+                        instruction.referral_share_bps,
+                    )
+                })
+            }
+            Instructions::SetPaused(instruction) => {
+                msg!("Instruction: SetPaused");
+                update_config(accounts, |config, admin| {
+                    config.set_paused(admin, instruction.paused)
+                })
+            }
+            Instructions::TransferAdmin(instruction) => {
+                msg!("Instruction: TransferAdmin");
+                update_config(accounts, |config, admin| {
+                    config.transfer_admin(admin, instruction.new_admin)
+                })
+            }
+            Instructions::RegisterReferral => {
+                msg!("Instruction: RegisterReferral");
+                register_referral(accounts)
+            }
+            Instructions::PlaceOrder(instruction) => {
+                msg!("Instruction: PlaceOrder");
+                place_order(accounts, instruction)
+            }
+            Instructions::FillOrder => {
+                msg!("Instruction: FillOrder");
+                check_config(accounts).and_then(|(config, accounts)| fill_order(accounts, &config))
+            }
+            Instructions::CancelOrder => {
+                msg!("Instruction: CancelOrder");
+                cancel_order(accounts)
+            }
+            Instructions::CreateDca(instruction) => {
+                msg!("Instruction: CreateDca");
+                create_dca(accounts, instruction)
+            }
+            Instructions::CrankDca => {
+                msg!("Instruction: CrankDca");
+                check_config(accounts).and_then(|(config, accounts)| crank_dca(accounts, &config))
+            }
+            Instructions::CloseDca => {
+                msg!("Instruction: CloseDca");
+                close_dca(accounts)
+            }
+            Instructions::AcceptAdmin => {
+                msg!("Instruction: AcceptAdmin");
+                update_config(accounts, |config, new_admin| config.accept_admin(new_admin))
+            }
+        };
+
+        // Most of the errors collapse into a generic `ProgramError`, log the precise one
+        result.map_err(|err| {
+            msg!("Error: {}", err);
+            ProgramError::from(err)
+        })
+    }
+
+    /// Swaps take the router config last, after the accounts of their venue, so the accounts
+    /// of `ExecuteSwap` clients predating the config keep their positions. Strips it after
+    /// checking the router is not paused.
+    fn check_config<'a, 'info>(
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<(RouterConfig, &'a [AccountInfo<'info>]), Error> {
+        let (config, accounts) = accounts
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        let config = RouterConfig::load(config)?;
+        config.check_active()?;
+        Ok((config, accounts))
+    }
+
+    fn initialize(
+        accounts: &[AccountInfo],
+        instruction: InitializeInstruction,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let config = next_account_info(&mut iter)?;
+        let admin = next_account_info(&mut iter)?;
+        let program_data = next_account_info(&mut iter)?;
+        let system_program = next_account_info(&mut iter)?;
+
+        if !admin.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        if *system_program.key != SYSTEM_PROGRAM {
+            return Err(Error::InvalidSystemProgram);
+        }
+
+        if upgrade_authority(program_data)? != Some(*admin.key) {
+            return Err(Error::InvalidUpgradeAuthority);
+        }
+
+        let (config_address, bump) = derive_config();
+        if *config.key != config_address {
+            return Err(Error::InvalidRouterConfigAddress);
+        }
+
+        check_fees(instruction.platform_fee_bps, instruction.referral_share_bps)?;
+
+        invoke_signed(
+            &token_cpi::create_account(
+                admin.key,
+                config.key,
+                Rent::get()?.minimum_balance(RouterConfig::LEN),
+                RouterConfig::LEN as u64,
+                &crate::id(),
+            ),
+            accounts,
+            &[&[b"config", &[bump]]],
+        )?;
+
+        RouterConfig::new(
+            *admin.key,
+            instruction.platform_fee_bps,
+            instruction.fee_destination,
+            instruction.referral_share_bps,
+            bump,
+        )
+        .store(config)
+    }
+
+    fn register_referral(accounts: &[AccountInfo]) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let referral = next_account_info(&mut iter)?;
+        let referrer = next_account_info(&mut iter)?;
+        let quote_mint = next_account_info(&mut iter)?;
+        let system_program = next_account_info(&mut iter)?;
+
+        if !referrer.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        if *system_program.key != SYSTEM_PROGRAM {
+            return Err(Error::InvalidSystemProgram);
+        }
+
+        if *quote_mint.owner != SPL_TOKEN_PROGRAM && *quote_mint.owner != SPL_TOKEN_2022_PROGRAM {
+            return Err(Error::InvalidTokenProgram);
+        }
+
+        let (referral_address, bump) = derive_referral(referrer.key, quote_mint.key);
+        if *referral.key != referral_address {
+            return Err(Error::InvalidReferralAddress);
+        }
+
+        invoke_signed(
+            &token_cpi::create_account(
+                referrer.key,
+                referral.key,
+                Rent::get()?.minimum_balance(Referral::LEN),
+                Referral::LEN as u64,
+                &crate::id(),
+            ),
+            accounts,
+            &[&[
+                b"referral",
+                referrer.key.as_ref(),
+                quote_mint.key.as_ref(),
+                &[bump],
+            ]],
+        )?;
+
+        Referral::new(*referrer.key, *quote_mint.key, bump).store(referral)
+    }
+
+    /// Applies `update` to the config on behalf of its admin.
+    /// Applies `update` with the signer following the config, which checks the signer.
+    fn update_config(
+        accounts: &[AccountInfo],
+        update: impl FnOnce(&mut RouterConfig, &AccountInfo) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let config_account = next_account_info(&mut iter)?;
+        let signer = next_account_info(&mut iter)?;
+
+        let mut config = RouterConfig::load(config_account)?;
+        update(&mut config, signer)?;
+        config.store(config_account)
+    }
+
+    pub fn read_pool(accounts: &[AccountInfo]) -> Result<PoolData, Error> {
+        let mut accounts = accounts.iter();
+        let pool = next_account_info(&mut accounts)?;
+        let global_config = next_account_info(&mut accounts)?;
+        let pool_base_ata = next_account_info(&mut accounts)?;
+        let pool_quote_ata = next_account_info(&mut accounts)?;
+
+        PoolData::new(pool, global_config, pool_base_ata, pool_quote_ata)
+    }
+
+    /// The router config may follow the pool accounts, the quote then includes the platform
+    /// fee.
+    fn quote(accounts: &[AccountInfo], instruction: QuoteInstruction) -> Result<(), Error> {
+        let mut pool_state = read_pool(accounts)?;
+        if let Some(config) = accounts.get(4) {
+            pool_state =
+                pool_state.with_platform_fee_bps(RouterConfig::load(config)?.platform_fee_bps);
+        }
+
+        pool_state
+            .global_config
+            .check_enabled(match instruction.direction {
+                Direction::Buy => DisableFlags::BUY,
+                Direction::Sell => DisableFlags::SELL,
+            })?;
+
+        let quote = pool_state.quote(instruction.direction, instruction.amount)?;
+        msg!("{:?}", quote);
+
+        quote.set_return_data()
+    }
+
+    fn sub_slippage(amount: u64, slippage_bps: u64) -> Result<u64, ProgramError> {
+        let slippage = ((amount as u128)
+            .checked_mul(slippage_bps as u128)
+            .and_then(|x| x.checked_div(10000u128))
+            .ok_or(ProgramError::ArithmeticOverflow))? as u64;
+
+        let amount = amount
+            .checked_sub(slippage)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(amount)
+    }
+
+    fn add_slippage(amount: u64, slippage_bps: u64) -> Result<u64, ProgramError> {
+        let slippage = ((amount as u128)
+            .checked_mul(slippage_bps as u128)
+            .and_then(|x| x.checked_div(10000u128))
+            .ok_or(ProgramError::ArithmeticOverflow))? as u64;
+
+        let amount = amount
+            .checked_add(slippage)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(amount)
+    }
+
+    impl<'a, 'info> SwapAccounts<'a, 'info> {
+        /// Swaps on behalf of a user that is a router PDA, which signs through `invoke_signed`
+        /// with `signer_seeds` instead of the transaction.
+        fn with_signer(mut self, signer_seeds: Vec<Vec<u8>>) -> Self {
+            self.signer_seeds = signer_seeds;
+            self
+        }
+
+        /// Invokes `instruction` with the user signature.
+        fn invoke_as_user(
+            &self,
+            instruction: &Instruction,
+            accounts: &[AccountInfo],
+        ) -> ProgramResult {
+            if self.signer_seeds.is_empty() {
+                return invoke(instruction, accounts);
+            }
+
+            let seeds: Vec<&[u8]> = self.signer_seeds.iter().map(Vec::as_slice).collect();
+            invoke_signed(instruction, accounts, &[&seeds])
+        }
+
+        /// Base and quote balances of the user token accounts.
+        fn user_balances(&self) -> Result<(u64, u64), Error> {
+            let base = TokenAccount::new(self.user_base_ata)?;
+            let quote = TokenAccount::new(self.user_quote_ata)?;
+            if base.mint != *self.base.key || quote.mint != *self.quote.key {
+                return Err(Error::MintMismatch);
+            }
+
+            Ok((base.amount, quote.amount))
+        }
+
+        /// Moves `amount` of quote out of the user quote token account.
+        fn transfer_quote(
+            &self,
+            accounts: &[AccountInfo],
+            destination: &AccountInfo,
+            amount: u64,
+        ) -> Result<(), Error> {
+            if amount == 0 {
+                return Ok(());
+            }
+
+            let decimals = mint_decimals(self.quote)?;
+
+            self.invoke_as_user(
+                &token_cpi::transfer_checked(
+                    self.quote_token_program.key,
+                    self.user_quote_ata.key,
+                    self.quote.key,
+                    destination.key,
+                    self.user.key,
+                    amount,
+                    decimals,
+                ),
+                accounts,
+            )?;
+
+            Ok(())
+        }
+
+        /// Creates the user base token account if needed, with the token program owning the
+        /// base mint.
+        fn create_user_base_ata(&self, accounts: &[AccountInfo]) -> Result<(), Error> {
+            self.check_user_base_ata()?;
+
+            self.invoke_as_user(
+                &token_cpi::create_associated_token_account_idempotent(
+                    self.user.key,
+                    self.user.key,
+                    self.base.key,
+                    self.base.owner,
+                ),
+                accounts,
+            )?;
+
+            Ok(())
+        }
+
+        /// Creates the user WSOL account if needed and wraps `lamports` into it. The account must
+        /// not hold WSOL yet, [`SwapAccounts::unwrap_sol`] closes it and would unwrap that WSOL
+        /// as well.
+        fn wrap_sol(&self, accounts: &[AccountInfo], lamports: u64) -> Result<(), Error> {
+            if *self.quote.key != NATIVE_MINT {
+                return Err(Error::NativeSolUnsupported);
+            }
+
+            let (wsol_ata, _) = derive_associated_token_account(
+                self.user.key,
+                self.quote_token_program.key,
+                self.quote.key,
+            );
+            if *self.user_quote_ata.key != wsol_ata {
+                return Err(Error::InvalidUserAta);
+            }
+
+            self.invoke_as_user(
+                &token_cpi::create_associated_token_account_idempotent(
+                    self.user.key,
+                    self.user.key,
+                    self.quote.key,
+                    self.quote_token_program.key,
+                ),
+                accounts,
+            )?;
+
+            if TokenAccount::new(self.user_quote_ata)?.amount > 0 {
+                msg!("User WSOL account already holds WSOL");
+                return Err(Error::WsolAccountNotEmpty);
+            }
+
+            if lamports > 0 {
+                self.invoke_as_user(
+                    &token_cpi::transfer(self.user.key, self.user_quote_ata.key, lamports),
+                    accounts,
+                )?;
+                self.invoke_as_user(
+                    &token_cpi::sync_native(self.quote_token_program.key, self.user_quote_ata.key),
+                    accounts,
+                )?;
+            }
+
+            Ok(())
+        }
+
+        /// Closes the user WSOL account, its whole balance goes back to the user as lamports.
+        fn unwrap_sol(&self, accounts: &[AccountInfo]) -> Result<(), Error> {
+            self.invoke_as_user(
+                &token_cpi::close_account(
+                    self.quote_token_program.key,
+                    self.user_quote_ata.key,
+                    self.user.key,
+                    self.user.key,
+                ),
+                accounts,
+            )?;
+
+            Ok(())
+        }
+
+        /// Builds the swap event, reserves are read from the pool token accounts so they reflect
+        /// the state after the swap.
+        fn swap_performed(
+            &self,
+            direction: Direction,
+            input_amount: u64,
+            output_amount: u64,
+            fees: Fees,
+        ) -> Result<SwapPerformed, Error> {
+            Ok(SwapPerformed {
+                pool: *self.pool.key,
+                user: *self.user.key,
+                base_mint: *self.base.key,
+                quote_mint: *self.quote.key,
+                direction,
+                input_amount,
+                output_amount,
+                lp_fee: fees.lp_fee,
+                protocol_fee: fees.protocol_fee,
+                coin_creator_fee: fees.coin_creator_fee,
+                reserve_base: TokenAccount::new(self.pool_base_ata)?.amount,
+                reserve_quote: TokenAccount::new(self.pool_quote_ata)?.amount,
+            })
+        }
+
+        /// Runs every account check and decodes the pool state.
+        fn load_pool(&self) -> Result<PoolData, Error> {
+            if self.signer_seeds.is_empty() && !self.user.is_signer {
+                msg!("Missing user signature");
+                return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+            }
+
+            self.check_programs()?;
+
+            let pool_state = self.pool_data()?;
+            msg!("Pool: {:?}", pool_state);
+
+            self.check_pool_accounts(&pool_state)?;
+
+            Ok(pool_state)
+        }
+
+        /// Invokes PumpSwap `sell` and returns the base spent and the quote received, both
+        /// measured on the user token accounts.
+        fn invoke_sell(
+            &self,
+            accounts: &[AccountInfo],
+            sell: pumpfun_cpi::Sell,
+        ) -> Result<(u64, u64), Error> {
+            msg!("Sell instruction: {:?}", sell);
+
+            let sell = pumpfun_cpi::sell(
+                self.pool.key,
+                self.user.key,
+                self.global_config.key,
+                self.base.key,
+                self.quote.key,
+                self.user_base_ata.key,
+                self.user_quote_ata.key,
+                self.pool_base_ata.key,
+                self.pool_quote_ata.key,
+                self.protocol_fee_recipient.key,
+                self.protocol_fee_recipient_ata.key,
+                self.base_token_program.key,
+                self.quote_token_program.key,
+                self.system_program.key,
+                self.associated_token_program.key,
+                self.event_authority.key,
+                self.pumpfun_program.key,
+                self.coin_creator_vault_ata.key,
+                self.coin_creator_vault_authority.key,
+                sell,
+            )?;
+
+            let (base_before, quote_before) = self.user_balances()?;
+            self.invoke_as_user(&sell, accounts)?;
+            let (base_after, quote_after) = self.user_balances()?;
+
+            let input_amount = base_before
+                .checked_sub(base_after)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let output_amount = quote_after
+                .checked_sub(quote_before)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok((input_amount, output_amount))
+        }
+    }
+
+    /// Accounts of the PumpSwap `buy` instruction.
+    struct BuyAccounts<'a, 'info> {
+        swap: SwapAccounts<'a, 'info>,
+        global_volume_accumulator: &'a AccountInfo<'info>,
+        user_volume_accumulator: &'a AccountInfo<'info>,
+    }
+
+    impl<'a, 'info> BuyAccounts<'a, 'info> {
+        fn next(iter: &mut Iter<'a, AccountInfo<'info>>) -> Result<Self, Error> {
+            Ok(BuyAccounts {
+                swap: SwapAccounts::next(iter)?,
+                global_volume_accumulator: next_account_info(iter)?,
+                user_volume_accumulator: next_account_info(iter)?,
+            })
+        }
+
+        fn with_signer(mut self, signer_seeds: Vec<Vec<u8>>) -> Self {
+            self.swap = self.swap.with_signer(signer_seeds);
+            self
+        }
+
+        /// Invokes PumpSwap `buy` and returns the quote spent and the base received, both
+        /// measured on the user token accounts.
+        fn invoke(
+            &self,
+            accounts: &[AccountInfo],
+            buy: pumpfun_cpi::Buy,
+        ) -> Result<(u64, u64), Error> {
+            let swap = &self.swap;
+            msg!("Buy instruction: {:?}", buy);
+
+            let buy = pumpfun_cpi::buy(
+                swap.pool.key,
+                swap.user.key,
+                swap.global_config.key,
+                swap.base.key,
+                swap.quote.key,
+                swap.user_base_ata.key,
+                swap.user_quote_ata.key,
+                swap.pool_base_ata.key,
+                swap.pool_quote_ata.key,
+                swap.protocol_fee_recipient.key,
+                swap.protocol_fee_recipient_ata.key,
+                swap.base_token_program.key,
+                swap.quote_token_program.key,
+                swap.system_program.key,
+                swap.associated_token_program.key,
+                swap.event_authority.key,
+                swap.pumpfun_program.key,
+                swap.coin_creator_vault_ata.key,
+                swap.coin_creator_vault_authority.key,
+                self.global_volume_accumulator.key,
+                self.user_volume_accumulator.key,
+                buy,
+            )?;
+
+            // PumpSwap enforces its own bounds, but the router only trusts what actually
+            // moved on the user accounts
+            let (base_before, quote_before) = swap.user_balances()?;
+            swap.invoke_as_user(&buy, accounts)?;
+            let (base_after, quote_after) = swap.user_balances()?;
+
+            let input_amount = quote_before
+                .checked_sub(quote_after)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let output_amount = base_after
+                .checked_sub(base_before)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok((input_amount, output_amount))
+        }
+    }
+
+    fn execute_swap(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: BuyInstruction,
+    ) -> Result<(), Error> {
+        execute_swap_signed(accounts, config, instruction, Vec::new())
+    }
+
+    /// [`execute_swap`] for a user that is a router PDA signing with `signer_seeds`.
+    fn execute_swap_signed(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: BuyInstruction,
+        signer_seeds: Vec<Vec<u8>>,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let buy_accounts = BuyAccounts::next(&mut iter)?.with_signer(signer_seeds);
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut iter, instruction.referrer)?;
+        let swap = &buy_accounts.swap;
+
+        if instruction.slippage_bps >= 10_000 {
+            msg!("Slippage too high: {}", instruction.slippage_bps);
+            return Err(Error::SlippageTooHigh);
+        }
+
+        if instruction.valid_until_slot.is_some() {
+            instruction.check_valid_until(Clock::get()?.slot)?;
+        }
+
+        let pool_state = swap
+            .load_pool()?
+            .with_platform_fee_bps(config.platform_fee_bps);
+        pool_state.global_config.check_enabled(DisableFlags::BUY)?;
+
+        if let Some(expected) = &instruction.expected_reserves {
+            if let Err(err) = pool_state.check_reserves(expected) {
+                msg!(
+                    "Reserves {}/{} drifted from {:?}",
+                    pool_state.reserve_base,
+                    pool_state.reserve_quote,
+                    expected
+                );
+                return Err(err);
+            }
+        }
+
+        // 1) Calculate expected base out
+        // 2) Find what slippage is allowed
+        // 3) Remove slippage from the base out
+        let base_out = pool_state.base_out(instruction.input_amount)?;
+        pool_state.check_price_impact(
+            Direction::Buy,
+            instruction.input_amount,
+            base_out,
+            instruction.max_price_impact_bps,
+        )?;
+        let base_out = sub_slippage(base_out, instruction.slippage_bps)?;
+
+        if instruction.native_sol {
+            swap.wrap_sol(accounts, instruction.input_amount)?;
+        }
+
+        swap.create_user_base_ata(accounts)?;
+
+        let platform_fee = pool_state.platform_fee(instruction.input_amount)?;
+        platform_fee_accounts.collect(accounts, config, swap, platform_fee)?;
+
+        let (input_amount, output_amount) = buy_accounts.invoke(
+            accounts,
+            pumpfun_cpi::Buy {
+                base_amount_out: base_out,
+                max_quote_amount_in: instruction.input_amount - platform_fee,
+            },
+        )?;
+
+        check_output(output_amount, instruction.min_base_out)?;
+
+        if instruction.native_sol {
+            swap.unwrap_sol(accounts)?;
+        }
+
+        let input_amount = input_amount
+            .checked_add(platform_fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let (_, fees) = pool_state.quote_in(output_amount)?;
+        swap.swap_performed(Direction::Buy, input_amount, output_amount, fees)?
+            .publish()
+    }
+
+    fn execute_buy_exact_out(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: BuyExactOutInstruction,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let buy_accounts = BuyAccounts::next(&mut iter)?;
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut iter, instruction.referrer)?;
+        let swap = &buy_accounts.swap;
+
+        if instruction.slippage_bps >= 10_000 {
+            msg!("Slippage too high: {}", instruction.slippage_bps);
+            return Err(Error::SlippageTooHigh);
+        }
+
+        let pool_state = swap
+            .load_pool()?
+            .with_platform_fee_bps(config.platform_fee_bps);
+        pool_state.global_config.check_enabled(DisableFlags::BUY)?;
+
+        // 1) Calculate the quote needed for the exact base out, fees included
+        // 2) Allow slippage on top of it and the platform fee, taken on top of the quote
+        //    PumpSwap charges
+        // 3) Cap the PumpSwap quote so that the user spends at most that with the platform fee
+        let (quote_in, fees) = pool_state.quote_in(instruction.base_amount_out)?;
+        let max_total_in = add_slippage(
+            quote_in
+                .checked_add(pool_state.platform_fee(quote_in)?)
+                .ok_or(ProgramError::ArithmeticOverflow)?,
+            instruction.slippage_bps,
+        )?;
+        let max_quote_in = pool_state.max_quote_before_platform_fee(max_total_in)?;
+
+        if instruction.native_sol {
+            swap.wrap_sol(accounts, max_total_in)?;
+        }
+
+        swap.create_user_base_ata(accounts)?;
+
+        let (input_amount, output_amount) = buy_accounts.invoke(
+            accounts,
+            pumpfun_cpi::Buy {
+                base_amount_out: instruction.base_amount_out,
+                max_quote_amount_in: max_quote_in,
+            },
+        )?;
+
+        check_output(output_amount, instruction.base_amount_out)?;
+
+        let platform_fee = pool_state.platform_fee(input_amount)?;
+        platform_fee_accounts.collect(accounts, config, swap, platform_fee)?;
+
+        if instruction.native_sol {
+            swap.unwrap_sol(accounts)?;
+        }
+
+        let input_amount = input_amount
+            .checked_add(platform_fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        swap.swap_performed(Direction::Buy, input_amount, output_amount, fees)?
+            .publish()
+    }
+
+    fn execute_sell(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: SellInstruction,
+    ) -> Result<(), Error> {
+        execute_sell_signed(accounts, config, instruction, Vec::new())
+    }
+
+    /// [`execute_sell`] for a user that is a router PDA signing with `signer_seeds`.
+    fn execute_sell_signed(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: SellInstruction,
+        signer_seeds: Vec<Vec<u8>>,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let swap = SwapAccounts::next(&mut iter)?.with_signer(signer_seeds);
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut iter, instruction.referrer)?;
+
+        if instruction.slippage_bps >= 10_000 {
+            msg!("Slippage too high: {}", instruction.slippage_bps);
+            return Err(Error::SlippageTooHigh);
+        }
+
+        let pool_state = swap
+            .load_pool()?
+            .with_platform_fee_bps(config.platform_fee_bps);
+        pool_state.global_config.check_enabled(DisableFlags::SELL)?;
+
+        // 1) Calculate expected quote out
+        // 2) Find what slippage is allowed
+        // 3) Remove slippage from the quote out, PumpSwap pays out before the platform fee
+        let quote_out = pool_state.quote_out(instruction.input_amount)?;
+        pool_state.check_price_impact(
+            Direction::Sell,
+            instruction.input_amount,
+            quote_out,
+            instruction.max_price_impact_bps,
+        )?;
+        let quote_out = pool_state.quote_out_before_platform_fee(instruction.input_amount)?;
+        let quote_out = sub_slippage(quote_out, instruction.slippage_bps)?;
+
+        // The WSOL account has to exist to receive the quote
+        if instruction.native_sol {
+            swap.wrap_sol(accounts, 0)?;
+        }
+
+        let (input_amount, output_amount) = swap.invoke_sell(
+            accounts,
+            pumpfun_cpi::Sell {
+                base_amount_in: instruction.input_amount,
+                min_quote_amount_out: quote_out,
+            },
+        )?;
+
+        let platform_fee = pool_state.platform_fee(output_amount)?;
+        platform_fee_accounts.collect(accounts, config, &swap, platform_fee)?;
+
+        let output_amount = output_amount
+            .checked_sub(platform_fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        check_output(output_amount, instruction.min_quote_out)?;
+
+        if instruction.native_sol {
+            swap.unwrap_sol(accounts)?;
+        }
+
+        let fees = pool_state.fees(pool_state.quote_out_before_fees(input_amount)?)?;
+        swap.swap_performed(Direction::Sell, input_amount, output_amount, fees)?
+            .publish()
+    }
+
+    /// Runs one hop of a route on the accounts at the front of `iter`. The pool state is read in
+    /// the same instruction as the CPI, so the hop needs no slippage of its own. The platform
+    /// fee is taken from the hop quote when `platform_fee` is set.
+    fn execute_hop<'a, 'info>(
+        accounts: &'a [AccountInfo<'info>],
+        iter: &mut Iter<'a, AccountInfo<'info>>,
+        direction: Direction,
+        input_amount: u64,
+        platform_fee: Option<(&RouterConfig, &PlatformFeeAccounts)>,
+    ) -> Result<(SwapPerformed, Pubkey, Pubkey), Error> {
+        let platform_fee_bps = platform_fee.map_or(0, |(config, _)| config.platform_fee_bps);
+        let (swap, event) = match direction {
+            Direction::Buy => {
+                let buy_accounts = BuyAccounts::next(iter)?;
+                let swap = &buy_accounts.swap;
+
+                let pool_state = swap.load_pool()?.with_platform_fee_bps(platform_fee_bps);
+                pool_state.global_config.check_enabled(DisableFlags::BUY)?;
+
+                let base_out = pool_state.base_out(input_amount)?;
+                swap.create_user_base_ata(accounts)?;
+
+                let fee = pool_state.platform_fee(input_amount)?;
+                if let Some((config, platform_fee_accounts)) = platform_fee {
+                    platform_fee_accounts.collect(accounts, config, swap, fee)?;
+                }
+
+                let (input_amount, output_amount) = buy_accounts.invoke(
+                    accounts,
+                    pumpfun_cpi::Buy {
+                        base_amount_out: base_out,
+                        max_quote_amount_in: input_amount - fee,
+                    },
+                )?;
+
+                let input_amount = input_amount
+                    .checked_add(fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                let (_, fees) = pool_state.quote_in(output_amount)?;
+                let event =
+                    swap.swap_performed(Direction::Buy, input_amount, output_amount, fees)?;
+                (buy_accounts.swap, event)
+            }
+            Direction::Sell => {
+                let swap = SwapAccounts::next(iter)?;
+
+                let pool_state = swap.load_pool()?.with_platform_fee_bps(platform_fee_bps);
+                pool_state.global_config.check_enabled(DisableFlags::SELL)?;
+
+                let quote_out = pool_state.quote_out_before_platform_fee(input_amount)?;
+                let (input_amount, output_amount) = swap.invoke_sell(
+                    accounts,
+                    pumpfun_cpi::Sell {
+                        base_amount_in: input_amount,
+                        min_quote_amount_out: quote_out,
+                    },
+                )?;
+
+                let fee = pool_state.platform_fee(output_amount)?;
+                if let Some((config, platform_fee_accounts)) = platform_fee {
+                    platform_fee_accounts.collect(accounts, config, &swap, fee)?;
+                }
+
+                let output_amount = output_amount
+                    .checked_sub(fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                let fees = pool_state.fees(pool_state.quote_out_before_fees(input_amount)?)?;
+                let event =
+                    swap.swap_performed(Direction::Sell, input_amount, output_amount, fees)?;
+                (swap, event)
+            }
+        };
+
+        // Input and output token accounts of the hop
+        let (input, output) = match direction {
+            Direction::Buy => (swap.user_quote_ata, swap.user_base_ata),
+            Direction::Sell => (swap.user_base_ata, swap.user_quote_ata),
+        };
+
+        Ok((event, *input.key, *output.key))
+    }
+
+    fn execute_route_swap(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: RouteSwapInstruction,
+    ) -> Result<(), Error> {
+        if instruction.hops.is_empty() {
+            msg!("Route has no hops");
+            return Err(Error::ProgramError(ProgramError::InvalidInstructionData));
+        }
+
+        // The platform fee accounts follow the accounts of every hop
+        let mut peek = accounts.iter();
+        for direction in &instruction.hops {
+            match direction {
+                Direction::Buy => drop(BuyAccounts::next(&mut peek)?),
+                Direction::Sell => drop(SwapAccounts::next(&mut peek)?),
+            }
+        }
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut peek, instruction.referrer)?;
+
+        let mut iter = accounts.iter();
+        let mut amount = instruction.input_amount;
+        let mut previous_output: Option<Pubkey> = None;
+        let mut last = None;
+
+        for (i, direction) in instruction.hops.into_iter().enumerate() {
+            // Every hop trades against quote, the first one pays the platform fee
+            let platform_fee = Some((config, &platform_fee_accounts)).filter(|_| i == 0);
+            let (event, input, output) =
+                execute_hop(accounts, &mut iter, direction, amount, platform_fee)?;
+            msg!("Hop {}: {:?}", i, event);
+
+            // Each hop has to spend from the account the previous one received into
+            if previous_output.is_some_and(|previous| previous != input) {
+                return Err(Error::RouteMismatch);
+            }
+
+            event.emit()?;
+            amount = event.output_amount;
+            previous_output = Some(output);
+            last = Some(event);
+        }
+
+        check_output(amount, instruction.min_output)?;
+
+        let last = last.ok_or(ProgramError::InvalidInstructionData)?;
+        SwapResult {
+            input_amount: instruction.input_amount,
+            ..SwapResult::from(&last)
+        }
+        .set_return_data()
+    }
+
+    fn execute_split_buy(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: SplitBuyInstruction,
+    ) -> Result<(), Error> {
+        if instruction.slippage_bps >= 10_000 {
+            msg!("Slippage too high: {}", instruction.slippage_bps);
+            return Err(Error::SlippageTooHigh);
+        }
+
+        let mut iter = accounts.iter();
+        let legs = (0..instruction.pools)
+            .map(|_| BuyAccounts::next(&mut iter))
+            .collect::<Result<Vec<_>, _>>()?;
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut iter, instruction.referrer)?;
+        let first = &legs
+            .first()
+            .ok_or(ProgramError::InvalidInstructionData)?
+            .swap;
+
+        // Every pool is read before any leg trades, so the same pool twice would be priced on
+        // stale reserves
+        let mut pools = Vec::with_capacity(legs.len());
+        for (i, leg) in legs.iter().enumerate() {
+            let swap = &leg.swap;
+            if swap.base.key != first.base.key
+                || swap.quote.key != first.quote.key
+                || swap.user_base_ata.key != first.user_base_ata.key
+                || swap.user_quote_ata.key != first.user_quote_ata.key
+                || legs[..i]
+                    .iter()
+                    .any(|other| other.swap.pool.key == swap.pool.key)
+            {
+                return Err(Error::InvalidSplit);
+            }
+
+            let pool_state = swap.load_pool()?;
+            pool_state.global_config.check_enabled(DisableFlags::BUY)?;
+            pools.push(pool_state);
+        }
+
+        // The platform fee is skimmed once from the whole input before it is split
+        let platform_fee = platform_fee(instruction.input_amount, config.platform_fee_bps)?;
+        let allocations = split_buy(&pools, instruction.input_amount - platform_fee)?;
+        msg!("Allocations: {:?}", allocations);
+
+        let mut expected = 0u64;
+        let mut base_outs = Vec::with_capacity(pools.len());
+        for (pool_state, quote_in) in pools.iter().zip(&allocations) {
+            let base_out = pool_state.base_out(*quote_in)?;
+            expected = expected
+                .checked_add(base_out)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            base_outs.push(base_out);
+        }
+
+        // One slippage check over the aggregate output
+        let min_base_out = sub_slippage(expected, instruction.slippage_bps)?;
+        let min_base_out = min_base_out.max(instruction.min_base_out);
+
+        first.create_user_base_ata(accounts)?;
+
+        platform_fee_accounts.collect(accounts, config, first, platform_fee)?;
+
+        let mut input_amount = platform_fee;
+        let mut output_amount = 0u64;
+        let mut last = None;
+        for ((leg, pool_state), (quote_in, base_out)) in legs
+            .iter()
+            .zip(&pools)
+            .zip(allocations.into_iter().zip(base_outs))
+        {
+            if base_out == 0 {
+                continue;
+            }
+
+            let (input, output) = leg.invoke(
+                accounts,
+                pumpfun_cpi::Buy {
+                    base_amount_out: base_out,
+                    max_quote_amount_in: quote_in,
+                },
+            )?;
+
+            let (_, fees) = pool_state.quote_in(output)?;
+            let event = leg
+                .swap
+                .swap_performed(Direction::Buy, input, output, fees)?;
+            event.emit()?;
+
+            input_amount = input_amount
+                .checked_add(input)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            output_amount = output_amount
+                .checked_add(output)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            last = Some(event);
+        }
+
+        check_output(output_amount, min_base_out)?;
+
+        let last = last.ok_or(Error::InsufficientOutput)?;
+        SwapResult {
+            input_amount,
+            output_amount,
+            ..SwapResult::from(&last)
+        }
+        .set_return_data()
+    }
+
+    /// Accounts after the swap accounts collecting the platform fee: the fee vault, the token
+    /// account of the fee destination, followed by the referrer token account and its referral
+    /// for a swap with a referrer. The bonding curve pays in lamports, its fee accounts are WSOL
+    /// accounts.
+    struct PlatformFeeAccounts<'a, 'info> {
+        fee_vault: &'a AccountInfo<'info>,
+        referrer: Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)>,
+    }
+
+    impl<'a, 'info> PlatformFeeAccounts<'a, 'info> {
+        /// The fee vault is always there, whatever the platform fee, so the accounts of a swap
+        /// do not change with the router config. The referrer accounts follow it when the
+        /// instruction says so.
+        fn next(iter: &mut Iter<'a, AccountInfo<'info>>, referrer: bool) -> Result<Self, Error> {
+            let fee_vault = next_account_info(iter)?;
+            let referrer = match referrer {
+                true => Some((next_account_info(iter)?, next_account_info(iter)?)),
+                false => None,
+            };
+
+            Ok(PlatformFeeAccounts {
+                fee_vault,
+                referrer,
+            })
+        }
+
+        /// Moves `fee` out of the user quote token account, the referrer share to the
+        /// referrer and the rest to the fee vault.
+        fn collect(
+            &self,
+            accounts: &[AccountInfo],
+            config: &RouterConfig,
+            swap: &SwapAccounts,
+            fee: u64,
+        ) -> Result<(), Error> {
+            self.collect_with(
+                config,
+                swap.user.key,
+                swap.quote.key,
+                swap.quote_token_program.key,
+                fee,
+                |destination, amount| swap.transfer_quote(accounts, destination, amount),
+            )
+        }
+
+        /// Moves `fee` lamports out of `user` on the bonding curve into the WSOL accounts of the
+        /// fee destination and the referrer, then syncs each of them so the token amount
+        /// counts the lamports, as [`SwapAccounts::wrap_sol`] does.
+        fn collect_lamports(
+            &self,
+            accounts: &[AccountInfo],
+            config: &RouterConfig,
+            user: &AccountInfo,
+            fee: u64,
+        ) -> Result<(), Error> {
+            self.collect_with(
+                config,
+                user.key,
+                &NATIVE_MINT,
+                &SPL_TOKEN_PROGRAM,
+                fee,
+                |destination, amount| {
+                    if amount > 0 {
+                        invoke(
+                            &token_cpi::transfer(user.key, destination.key, amount),
+                            accounts,
+                        )?;
+                        invoke(
+                            &token_cpi::sync_native(&SPL_TOKEN_PROGRAM, destination.key),
+                            accounts,
+                        )?;
+                    }
+
+                    Ok(())
+                },
+            )
+        }
+
+        /// Checks the fee accounts of a swap of `user` against `quote_mint` and pays the fee
+        /// shares with `pay`.
+        fn collect_with(
+            &self,
+            config: &RouterConfig,
+            user: &Pubkey,
+            quote_mint: &Pubkey,
+            quote_token_program: &Pubkey,
+            fee: u64,
+            pay: impl Fn(&AccountInfo, u64) -> Result<(), Error>,
+        ) -> Result<(), Error> {
+            let (fee_vault, _) = derive_associated_token_account(
+                &config.fee_destination,
+                quote_token_program,
+                quote_mint,
+            );
+            if *self.fee_vault.key != fee_vault {
+                return Err(Error::InvalidFeeVault);
+            }
+
+            let mut vault_fee = fee;
+            if let Some((referrer_ata, referral_account)) = self.referrer {
+                let mut referral = Referral::load(referral_account)?;
+                referral.check_referrer(quote_mint, &TokenAccount::new(referrer_ata)?, user)?;
+
+                let share = config.referral_share(fee)?;
+                pay(referrer_ata, share)?;
+
+                referral.fees_earned = referral
+                    .fees_earned
+                    .checked_add(share)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                referral.store(referral_account)?;
+
+                vault_fee -= share;
+            }
+
+            pay(self.fee_vault, vault_fee)
+        }
+    }
+
+    /// Accounts of a venue that decide what an arbitrage trades and where its profit lands.
+    struct VenueAccounts<'a, 'info> {
+        user: &'a AccountInfo<'info>,
+        base: &'a AccountInfo<'info>,
+        quote_mint: Pubkey,
+        /// `None` for the curve, which pays in lamports.
+        user_quote_ata: Option<&'a AccountInfo<'info>>,
+    }
+
+    impl<'a, 'info> VenueAccounts<'a, 'info> {
+        fn next(
+            iter: &mut Iter<'a, AccountInfo<'info>>,
+            venue: Venue,
+            direction: Direction,
+        ) -> Result<Self, Error> {
+            let swap = match (venue, direction) {
+                (Venue::Pool, Direction::Buy) => BuyAccounts::next(iter)?.swap,
+                (Venue::Pool, Direction::Sell) => SwapAccounts::next(iter)?,
+                (Venue::Curve, direction) => {
+                    let curve = match direction {
+                        Direction::Buy => CurveBuyAccounts::next(iter)?.curve,
+                        Direction::Sell => CurveAccounts::next_sell(iter)?,
+                    };
+
+                    return Ok(VenueAccounts {
+                        user: curve.user,
+                        base: curve.mint,
+                        quote_mint: NATIVE_MINT,
+                        user_quote_ata: None,
+                    });
+                }
+            };
+
+            Ok(VenueAccounts {
+                user: swap.user,
+                base: swap.base,
+                quote_mint: *swap.quote.key,
+                user_quote_ata: Some(swap.user_quote_ata),
+            })
+        }
+    }
+
+    /// Runs one leg of an arbitrage on the curve, the curve counterpart of [`execute_hop`].
+    fn execute_curve_hop<'a, 'info>(
+        accounts: &'a [AccountInfo<'info>],
+        iter: &mut Iter<'a, AccountInfo<'info>>,
+        direction: Direction,
+        input_amount: u64,
+        platform_fee: Option<(&RouterConfig, &PlatformFeeAccounts)>,
+    ) -> Result<SwapPerformed, Error> {
+        let platform_fee_bps = platform_fee.map_or(0, |(config, _)| config.platform_fee_bps);
+        match direction {
+            Direction::Buy => {
+                let buy_accounts = CurveBuyAccounts::next(iter)?;
+                let curve_state = buy_accounts.curve.load_curve()?;
+
+                let fee = crate::platform_fee(input_amount, platform_fee_bps)?;
+                if let Some((config, platform_fee_accounts)) = platform_fee {
+                    platform_fee_accounts.collect_lamports(
+                        accounts,
+                        config,
+                        buy_accounts.curve.user,
+                        fee,
+                    )?;
+                }
+
+                let base_out = curve_state.base_out(input_amount - fee)?;
+                buy_accounts.create_user_ata(accounts)?;
+
+                let (input_amount, output_amount) = buy_accounts.invoke(
+                    accounts,
+                    launchpad::cpi::Buy {
+                        amount: base_out,
+                        max_sol_cost: input_amount - fee,
+                    },
+                )?;
+
+                let input_amount = input_amount
+                    .checked_add(fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                let (_, fees) = curve_state.quote_in(output_amount)?;
+                buy_accounts
+                    .curve
+                    .swap_performed(Direction::Buy, input_amount, output_amount, fees)
+            }
+            Direction::Sell => {
+                let curve = CurveAccounts::next_sell(iter)?;
+                let curve_state = curve.load_curve()?;
+
+                let quote_out = curve_state.quote_out(input_amount)?;
+                let (input_amount, output_amount) = curve.invoke_sell(
+                    accounts,
+                    launchpad::cpi::Sell {
+                        amount: input_amount,
+                        min_sol_output: quote_out,
+                    },
+                )?;
+
+                let fee = crate::platform_fee(output_amount, platform_fee_bps)?;
+                if let Some((config, platform_fee_accounts)) = platform_fee {
+                    platform_fee_accounts.collect_lamports(accounts, config, curve.user, fee)?;
+                }
+
+                let output_amount = output_amount
+                    .checked_sub(fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                let fees = curve_state.fees(curve_state.quote_out_before_fees(input_amount)?)?;
+                curve.swap_performed(Direction::Sell, input_amount, output_amount, fees)
+            }
+        }
+    }
+
+    fn execute_arbitrage(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: ArbitrageInstruction,
+    ) -> Result<(), Error> {
+        // Peek at both venues first, the profit is measured around the whole cycle
+        let mut peek = accounts.iter();
+        let buy = VenueAccounts::next(&mut peek, instruction.buy_venue, Direction::Buy)?;
+        let sell = VenueAccounts::next(&mut peek, instruction.sell_venue, Direction::Sell)?;
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut peek, instruction.referrer)?;
+
+        if buy.user.key != sell.user.key
+            || buy.base.key != sell.base.key
+            || buy.quote_mint != sell.quote_mint
+        {
+            return Err(Error::InvalidArbitrage);
+        }
+
+        if let (Some(buy_ata), Some(sell_ata)) = (buy.user_quote_ata, sell.user_quote_ata) {
+            if buy_ata.key != sell_ata.key {
+                return Err(Error::InvalidArbitrage);
+            }
+        }
+
+        let user = buy.user;
+        let user_quote_ata = buy.user_quote_ata.or(sell.user_quote_ata);
+        // Only the curve trades lamports, which makes the quote mint WSOL. On a pool to pool
+        // cycle the user lamports merely pay rent and are left out of the profit.
+        let on_curve =
+            instruction.buy_venue == Venue::Curve || instruction.sell_venue == Venue::Curve;
+        let quote_balance = || -> Result<u64, Error> {
+            let tokens = match user_quote_ata {
+                Some(ata) => TokenAccount::new(ata)?.amount,
+                None => 0,
+            };
+
+            if !on_curve {
+                return Ok(tokens);
+            }
+
+            Ok(user
+                .lamports()
+                .checked_add(tokens)
+                .ok_or(ProgramError::ArithmeticOverflow)?)
+        };
+
+        let balance_before = quote_balance()?;
+
+        let mut iter = accounts.iter();
+        let mut events = Vec::with_capacity(2);
+        let mut amount = instruction.input_amount;
+        for (venue, direction) in [
+            (instruction.buy_venue, Direction::Buy),
+            (instruction.sell_venue, Direction::Sell),
+        ] {
+            // The buy leg pays the platform fee on the input
+            let platform_fee =
+                Some((config, &platform_fee_accounts)).filter(|_| direction == Direction::Buy);
+            let event = match venue {
+                Venue::Pool => execute_hop(accounts, &mut iter, direction, amount, platform_fee)?.0,
+                Venue::Curve => {
+                    execute_curve_hop(accounts, &mut iter, direction, amount, platform_fee)?
+                }
+            };
+
+            amount = event.output_amount;
+            events.push(event);
+        }
+
+        let balance_after = quote_balance()?;
+        let profit = balance_after.saturating_sub(balance_before);
+        if balance_after <= balance_before || profit < instruction.min_profit {
+            msg!(
+                "Quote balance {} -> {}, expected a profit of at least {}",
+                balance_before,
+                balance_after,
+                instruction.min_profit
+            );
+            return Err(Error::UnprofitableArbitrage);
+        }
+
+        for event in &events {
+            event.emit()?;
+        }
+
+        let [buy, sell] = events.as_slice() else {
+            return Err(Error::ProgramError(ProgramError::InvalidInstructionData));
+        };
+
+        SwapResult {
+            input_amount: buy.input_amount,
+            output_amount: sell.output_amount,
+            ..SwapResult::from(sell)
+        }
+        .set_return_data()
+    }
+
+    /// Accounts of the launchpad `buy` and `sell` instructions. `sell` lists the creator vault
+    /// before the token program, [`CurveAccounts::next_sell`] reads it in that order.
+    struct CurveAccounts<'a, 'info> {
+        global: &'a AccountInfo<'info>,
+        fee_recipient: &'a AccountInfo<'info>,
+        mint: &'a AccountInfo<'info>,
+        bonding_curve: &'a AccountInfo<'info>,
+        associated_bonding_curve: &'a AccountInfo<'info>,
+        user_ata: &'a AccountInfo<'info>,
+        user: &'a AccountInfo<'info>,
+        system_program: &'a AccountInfo<'info>,
+        token_program: &'a AccountInfo<'info>,
+        creator_vault: &'a AccountInfo<'info>,
+        event_authority: &'a AccountInfo<'info>,
+        launchpad_program: &'a AccountInfo<'info>,
+    }
+
+    impl<'a, 'info> CurveAccounts<'a, 'info> {
+        const SELL_LEN: usize = 12;
+
+        fn next_buy(iter: &mut Iter<'a, AccountInfo<'info>>) -> Result<Self, Error> {
+            Ok(CurveAccounts {
+                global: next_account_info(iter)?,
+                fee_recipient: next_account_info(iter)?,
+                mint: next_account_info(iter)?,
+                bonding_curve: next_account_info(iter)?,
+                associated_bonding_curve: next_account_info(iter)?,
+                user_ata: next_account_info(iter)?,
+                user: next_account_info(iter)?,
+                system_program: next_account_info(iter)?,
+                token_program: next_account_info(iter)?,
+                creator_vault: next_account_info(iter)?,
+                event_authority: next_account_info(iter)?,
+                launchpad_program: next_account_info(iter)?,
+            })
+        }
+
+        fn next_sell(iter: &mut Iter<'a, AccountInfo<'info>>) -> Result<Self, Error> {
+            Ok(CurveAccounts {
+                global: next_account_info(iter)?,
+                fee_recipient: next_account_info(iter)?,
+                mint: next_account_info(iter)?,
+                bonding_curve: next_account_info(iter)?,
+                associated_bonding_curve: next_account_info(iter)?,
+                user_ata: next_account_info(iter)?,
+                user: next_account_info(iter)?,
+                system_program: next_account_info(iter)?,
+                creator_vault: next_account_info(iter)?,
+                token_program: next_account_info(iter)?,
+                event_authority: next_account_info(iter)?,
+                launchpad_program: next_account_info(iter)?,
+            })
+        }
+
+        /// Pins every program the router passes to the launchpad, as for PumpSwap the user
+        /// signature is forwarded to `launchpad_program`.
+        fn check_programs(&self) -> Result<(), Error> {
+            if *self.launchpad_program.key != PUMPFUN_LAUNCHPAD_PROGRAM {
+                return Err(Error::InvalidLaunchpadProgram);
+            }
+
+            if *self.token_program.key != SPL_TOKEN_PROGRAM
+                && *self.token_program.key != SPL_TOKEN_2022_PROGRAM
+            {
+                return Err(Error::InvalidTokenProgram);
+            }
+
+            if self.mint.owner != self.token_program.key {
+                return Err(Error::InvalidTokenProgram);
+            }
+
+            if *self.system_program.key != SYSTEM_PROGRAM {
+                return Err(Error::InvalidSystemProgram);
+            }
+
+            Ok(())
+        }
+
+        /// Runs every account check and decodes the curve state.
+        fn load_curve(&self) -> Result<CurveData, Error> {
+            if !self.user.is_signer {
+                msg!("Missing user signature");
+                return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+            }
+
+            self.check_programs()?;
+
+            let curve_state = self.curve_data()?;
+            msg!("Curve: {:?}", curve_state);
+
+            if curve_state.curve.complete {
+                return Err(Error::CurveComplete);
+            }
+
+            self.check_curve_accounts(&curve_state)?;
+
+            Ok(curve_state)
+        }
+
+        fn curve_data(&self) -> Result<CurveData, Error> {
+            CurveData::new(self.bonding_curve, self.global, self.mint.key)
+        }
+
+        /// Checks the fee recipient, curve token account, creator vault and user token account
+        /// against the decoded curve state.
+        fn check_curve_accounts(&self, curve_state: &CurveData) -> Result<(), Error> {
+            let fee_recipient = self.fee_recipient.key;
+            if *fee_recipient == Pubkey::default()
+                || !curve_state
+                    .global
+                    .fee_recipients()
+                    .any(|recipient| recipient == fee_recipient)
+            {
+                return Err(Error::InvalidLaunchpadFeeRecipient);
+            }
+
+            let (associated_bonding_curve, _) = derive_associated_token_account(
+                self.bonding_curve.key,
+                self.token_program.key,
+                self.mint.key,
+            );
+            if *self.associated_bonding_curve.key != associated_bonding_curve {
+                return Err(Error::InvalidAssociatedBondingCurve);
+            }
+
+            let (creator_vault, _) = derive_creator_vault(&curve_state.curve.creator);
+            if *self.creator_vault.key != creator_vault {
+                return Err(Error::InvalidCreatorVault);
+            }
+
+            let (user_ata, _) = derive_associated_token_account(
+                self.user.key,
+                self.token_program.key,
+                self.mint.key,
+            );
+            if *self.user_ata.key != user_ata {
+                return Err(Error::InvalidUserAta);
+            }
+
+            Ok(())
+        }
+
+        /// Token balance of the user token account and lamports of the user.
+        fn user_balances(&self) -> Result<(u64, u64), Error> {
+            let base = TokenAccount::new(self.user_ata)?;
+            if base.mint != *self.mint.key {
+                return Err(Error::MintMismatch);
+            }
+
+            Ok((base.amount, self.user.lamports()))
+        }
+
+        /// Invokes the launchpad `sell` and returns the tokens spent and the lamports received.
+        fn invoke_sell(
+            &self,
+            accounts: &[AccountInfo],
+            sell: launchpad::cpi::Sell,
+        ) -> Result<(u64, u64), Error> {
+            msg!("Sell instruction: {:?}", sell);
+
+            let sell = launchpad::cpi::sell(
+                self.global.key,
+                self.fee_recipient.key,
+                self.mint.key,
+                self.bonding_curve.key,
+                self.associated_bonding_curve.key,
+                self.user_ata.key,
+                self.user.key,
+                self.system_program.key,
+                self.creator_vault.key,
+                self.token_program.key,
+                self.event_authority.key,
+                self.launchpad_program.key,
+                sell,
+            )?;
+
+            let (base_before, lamports_before) = self.user_balances()?;
+            invoke(&sell, accounts)?;
+            let (base_after, lamports_after) = self.user_balances()?;
+
+            let input_amount = base_before
+                .checked_sub(base_after)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let output_amount = lamports_after
+                .checked_sub(lamports_before)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok((input_amount, output_amount))
+        }
+
+        /// Builds the swap event for a curve trade, the bonding curve stands in for the pool and
+        /// its virtual reserves for the pool reserves.
+        fn swap_performed(
+            &self,
+            direction: Direction,
+            input_amount: u64,
+            output_amount: u64,
+            fees: Fees,
+        ) -> Result<SwapPerformed, Error> {
+            let curve = CurveData::read_curve(self.bonding_curve)?;
+
+            Ok(SwapPerformed {
+                pool: *self.bonding_curve.key,
+                user: *self.user.key,
+                base_mint: *self.mint.key,
+                quote_mint: NATIVE_MINT,
+                direction,
+                input_amount,
+                output_amount,
+                lp_fee: fees.lp_fee,
+                protocol_fee: fees.protocol_fee,
+                coin_creator_fee: fees.coin_creator_fee,
+                reserve_base: curve.virtual_token_reserves,
+                reserve_quote: curve.virtual_sol_reserves,
+            })
+        }
+    }
+
+    /// Accounts of the launchpad `buy` instruction, followed by the associated token program
+    /// the router needs to create the user token account.
+    struct CurveBuyAccounts<'a, 'info> {
+        curve: CurveAccounts<'a, 'info>,
+        global_volume_accumulator: &'a AccountInfo<'info>,
+        user_volume_accumulator: &'a AccountInfo<'info>,
+        associated_token_program: &'a AccountInfo<'info>,
+    }
+
+    impl<'a, 'info> CurveBuyAccounts<'a, 'info> {
+        const LEN: usize = 15;
+
+        fn next(iter: &mut Iter<'a, AccountInfo<'info>>) -> Result<Self, Error> {
+            Ok(CurveBuyAccounts {
+                curve: CurveAccounts::next_buy(iter)?,
+                global_volume_accumulator: next_account_info(iter)?,
+                user_volume_accumulator: next_account_info(iter)?,
+                associated_token_program: next_account_info(iter)?,
+            })
+        }
+
+        fn create_user_ata(&self, accounts: &[AccountInfo]) -> Result<(), Error> {
+            if *self.associated_token_program.key != ASSOCIATED_TOKEN_PROGRAM {
+                return Err(Error::InvalidAssociatedTokenProgram);
+            }
+
+            let curve = &self.curve;
+            invoke(
+                &token_cpi::create_associated_token_account_idempotent(
+                    curve.user.key,
+                    curve.user.key,
+                    curve.mint.key,
+                    curve.token_program.key,
+                ),
+                accounts,
+            )?;
+
+            Ok(())
+        }
+
+        /// Invokes the launchpad `buy` and returns the lamports spent and the tokens received.
+        fn invoke(
+            &self,
+            accounts: &[AccountInfo],
+            buy: launchpad::cpi::Buy,
+        ) -> Result<(u64, u64), Error> {
+            let curve = &self.curve;
+            msg!("Buy instruction: {:?}", buy);
+
+            let buy = launchpad::cpi::buy(
+                curve.global.key,
+                curve.fee_recipient.key,
+                curve.mint.key,
+                curve.bonding_curve.key,
+                curve.associated_bonding_curve.key,
+                curve.user_ata.key,
+                curve.user.key,
+                curve.system_program.key,
+                curve.token_program.key,
+                curve.creator_vault.key,
+                curve.event_authority.key,
+                curve.launchpad_program.key,
+                self.global_volume_accumulator.key,
+                self.user_volume_accumulator.key,
+                buy,
+            )?;
+
+            let (base_before, lamports_before) = curve.user_balances()?;
+            invoke(&buy, accounts)?;
+            let (base_after, lamports_after) = curve.user_balances()?;
+
+            let input_amount = lamports_before
+                .checked_sub(lamports_after)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let output_amount = base_after
+                .checked_sub(base_before)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Ok((input_amount, output_amount))
+        }
+    }
+
+    fn execute_curve_buy(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: CurveBuyInstruction,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let buy_accounts = CurveBuyAccounts::next(&mut iter)?;
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut iter, instruction.referrer)?;
+        let curve = &buy_accounts.curve;
+
+        if instruction.slippage_bps >= 10_000 {
+            msg!("Slippage too high: {}", instruction.slippage_bps);
+            return Err(Error::SlippageTooHigh);
+        }
+
+        let curve_state = curve.load_curve()?;
+
+        // The platform fee is skimmed before the lamports reach the curve
+        let platform_fee = platform_fee(instruction.input_amount, config.platform_fee_bps)?;
+        let max_sol_cost = instruction.input_amount - platform_fee;
+
+        // 1) Calculate expected base out from the virtual reserves
+        // 2) Find what slippage is allowed
+        // 3) Remove slippage from the base out
+        let base_out = curve_state.base_out(max_sol_cost)?;
+        let base_out = sub_slippage(base_out, instruction.slippage_bps)?;
+
+        buy_accounts.create_user_ata(accounts)?;
+
+        platform_fee_accounts.collect_lamports(accounts, config, curve.user, platform_fee)?;
+
+        let (input_amount, output_amount) = buy_accounts.invoke(
+            accounts,
+            launchpad::cpi::Buy {
+                amount: base_out,
+                max_sol_cost,
+            },
+        )?;
+
+        check_output(output_amount, instruction.min_base_out)?;
+
+        let input_amount = input_amount
+            .checked_add(platform_fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let (_, fees) = curve_state.quote_in(output_amount)?;
+        curve
+            .swap_performed(Direction::Buy, input_amount, output_amount, fees)?
+            .publish()
+    }
+
+    fn execute_curve_sell(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: CurveSellInstruction,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let curve = CurveAccounts::next_sell(&mut iter)?;
+        let platform_fee_accounts = PlatformFeeAccounts::next(&mut iter, instruction.referrer)?;
+
+        if instruction.slippage_bps >= 10_000 {
+            msg!("Slippage too high: {}", instruction.slippage_bps);
+            return Err(Error::SlippageTooHigh);
+        }
+
+        let curve_state = curve.load_curve()?;
+
+        // 1) Calculate expected lamports out from the virtual reserves
+        // 2) Find what slippage is allowed
+        // 3) Remove slippage from the lamports out, the curve pays out before the platform fee
+        let quote_out = curve_state.quote_out(instruction.input_amount)?;
+        let quote_out = sub_slippage(quote_out, instruction.slippage_bps)?;
 
-        if global_config.data.as_ref().borrow().len() < 353 {
-            return Err(Error::ProgramError(ProgramError::InvalidArgument));
+        let (input_amount, output_amount) = curve.invoke_sell(
+            accounts,
+            launchpad::cpi::Sell {
+                amount: instruction.input_amount,
+                min_sol_output: quote_out,
+            },
+        )?;
+
+        let platform_fee = platform_fee(output_amount, config.platform_fee_bps)?;
+        platform_fee_accounts.collect_lamports(accounts, config, curve.user, platform_fee)?;
+
+        let output_amount = output_amount
+            .checked_sub(platform_fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        check_output(output_amount, instruction.min_quote_out)?;
+
+        let fees = curve_state.fees(curve_state.quote_out_before_fees(input_amount)?)?;
+        curve
+            .swap_performed(Direction::Sell, input_amount, output_amount, fees)?
+            .publish()
+    }
+
+    /// Splits the accounts of a smart instruction into the curve and the pool accounts, and
+    /// tells whether the curve is still active.
+    fn route<'a, 'info>(
+        accounts: &'a [AccountInfo<'info>],
+        curve_len: usize,
+        curve: impl Fn(&mut Iter<'a, AccountInfo<'info>>) -> Result<CurveAccounts<'a, 'info>, Error>,
+    ) -> Result<(bool, &'a [AccountInfo<'info>], &'a [AccountInfo<'info>]), Error> {
+        if accounts.len() < curve_len {
+            return Err(Error::ProgramError(ProgramError::NotEnoughAccountKeys));
         }
 
-        let global_config =
-            PoolGlobalConfig::try_from_slice(&global_config.data.as_ref().borrow()[8..353])
-                .map_err(Error::BorshIoError)?;
+        let (curve_accounts, pool_accounts) = accounts.split_at(curve_len);
+        let curve = curve(&mut curve_accounts.iter())?;
+        let curve_state = curve.curve_data()?;
 
-        Ok(PoolData {
-            global_config,
-            pool,
-            reserve_base: **pool_base_ata.lamports.as_ref().borrow(),
-            reserve_quote: **pool_quote_ata.lamports.as_ref().borrow(),
-        })
+        // Both routes must trade the same token
+        let pool = SwapAccounts::next(&mut pool_accounts.iter())?;
+        if pool.base.key != curve.mint.key {
+            return Err(Error::MintMismatch);
+        }
+
+        Ok((!curve_state.curve.complete, curve_accounts, pool_accounts))
     }
 
-    pub fn base_out(&self, quote_amount_in: u64) -> Result<u64, ProgramError> {
-        // Effective quote in amount
-        let quote = self.apply_fees(quote_amount_in)?;
+    fn execute_smart_buy(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: SmartBuyInstruction,
+    ) -> Result<(), Error> {
+        let (on_curve, curve_accounts, pool_accounts) =
+            route(accounts, CurveBuyAccounts::LEN, CurveAccounts::next_buy)?;
 
-        // 1) Find pool reserve relation accounting for `quote_amount_in`
-        // 2) Multiply on quote
-        //
-        // base_out = (reserve_base * quote) / (reserve_quote + quote)
-        let num = (self.reserve_base as u128)
-            .checked_mul(quote as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if on_curve {
+            msg!("Route: bonding curve");
+            // The platform fee accounts follow the pool accounts
+            let mut iter = pool_accounts.iter();
+            BuyAccounts::next(&mut iter)?;
+            let accounts: Vec<AccountInfo> = curve_accounts.iter().chain(iter).cloned().collect();
+            return execute_curve_buy(
+                &accounts,
+                config,
+                CurveBuyInstruction {
+                    referrer: instruction.referrer,
+                    ..CurveBuyInstruction::new(
+                        instruction.input_amount,
+                        instruction.slippage_bps,
+                        instruction.min_base_out,
+                    )
+                },
+            );
+        }
 
-        let denom = (self.reserve_quote as u128)
-            .checked_add(quote as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
+        msg!("Route: PumpSwap");
+        execute_swap(
+            pool_accounts,
+            config,
+            BuyInstruction {
+                referrer: instruction.referrer,
+                ..BuyInstruction::new(
+                    instruction.input_amount,
+                    instruction.slippage_bps,
+                    instruction.min_base_out,
+                )
+                .with_native_sol()
+            },
+        )
+    }
 
-        let base_out = num
-            .checked_div(denom)
-            .ok_or(ProgramError::ArithmeticOverflow)? as u64;
+    fn execute_smart_sell(
+        accounts: &[AccountInfo],
+        config: &RouterConfig,
+        instruction: SmartSellInstruction,
+    ) -> Result<(), Error> {
+        let (on_curve, curve_accounts, pool_accounts) =
+            route(accounts, CurveAccounts::SELL_LEN, CurveAccounts::next_sell)?;
 
-        Ok(base_out)
+        if on_curve {
+            msg!("Route: bonding curve");
+            // The platform fee accounts follow the pool accounts
+            let mut iter = pool_accounts.iter();
+            SwapAccounts::next(&mut iter)?;
+            let accounts: Vec<AccountInfo> = curve_accounts.iter().chain(iter).cloned().collect();
+            return execute_curve_sell(
+                &accounts,
+                config,
+                CurveSellInstruction {
+                    referrer: instruction.referrer,
+                    ..CurveSellInstruction::new(
+                        instruction.input_amount,
+                        instruction.slippage_bps,
+                        instruction.min_quote_out,
+                    )
+                },
+            );
+        }
+
+        msg!("Route: PumpSwap");
+        execute_sell(
+            pool_accounts,
+            config,
+            SellInstruction {
+                referrer: instruction.referrer,
+                ..SellInstruction::new(
+                    instruction.input_amount,
+                    instruction.slippage_bps,
+                    instruction.min_quote_out,
+                )
+                .with_native_sol()
+            },
+        )
     }
 
-    fn apply_fees(&self, amount: u64) -> Result<u64, ProgramError> {
-        let total_fee_bp = self
-            .global_config
-            .lp_fee_basis_points
-            .checked_add(self.global_config.protocol_fee_basis_points)
-            .and_then(|x| x.checked_add(self.global_config.coin_creator_fee_basis_points))
-            .ok_or(ProgramError::ArithmeticOverflow)?;
+    /// `decimals` of an SPL Token or Token-2022 mint, after the mint authority option and the
+    /// supply.
+    fn mint_decimals(mint: &AccountInfo) -> Result<u8, Error> {
+        Ok(*mint
+            .data
+            .as_ref()
+            .borrow()
+            .get(44)
+            .ok_or(ProgramError::InvalidAccountData)?)
+    }
 
-        let fee_amount = (amount as u128)
-            .checked_mul(total_fee_bp as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(10000u128)
-            .ok_or(ProgramError::ArithmeticOverflow)? as u64;
+    /// Checks that `token_program` is a token program and owns `mint`.
+    fn check_token_program(mint: &AccountInfo, token_program: &AccountInfo) -> Result<(), Error> {
+        if *token_program.key != SPL_TOKEN_PROGRAM && *token_program.key != SPL_TOKEN_2022_PROGRAM {
+            return Err(Error::InvalidTokenProgram);
+        }
 
-        amount
-            .checked_sub(fee_amount)
-            .ok_or(ProgramError::ArithmeticOverflow)
+        if mint.owner != token_program.key {
+            return Err(Error::InvalidTokenProgram);
+        }
+
+        Ok(())
     }
-}
 
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    #[error(transparent)]
-    ProgramError(#[from] ProgramError),
+    /// Moves the whole balance of a token account owned by a router PDA to `destination`.
+    fn sweep_token_account(
+        accounts: &[AccountInfo],
+        token_program: &AccountInfo,
+        mint: &AccountInfo,
+        account: &AccountInfo,
+        destination: &AccountInfo,
+        authority: &AccountInfo,
+        signer_seeds: &[&[u8]],
+    ) -> Result<(), Error> {
+        let amount = TokenAccount::new(account)?.amount;
+        if amount > 0 {
+            invoke_signed(
+                &token_cpi::transfer_checked(
+                    token_program.key,
+                    account.key,
+                    mint.key,
+                    destination.key,
+                    authority.key,
+                    amount,
+                    mint_decimals(mint)?,
+                ),
+                accounts,
+                &[signer_seeds],
+            )?;
+        }
 
-    #[error(transparent)]
-    BorshIoError(borsh::io::Error),
+        Ok(())
+    }
 
-    #[error("insufficient pool reserve")]
-    InsufficientPoolReserve,
+    /// [`sweep_token_account`], then closes the account with the rent going to
+    /// `rent_destination`.
+    #[allow(clippy::too_many_arguments)]
+    fn drain_token_account(
+        accounts: &[AccountInfo],
+        token_program: &AccountInfo,
+        mint: &AccountInfo,
+        account: &AccountInfo,
+        destination: &AccountInfo,
+        authority: &AccountInfo,
+        rent_destination: &AccountInfo,
+        signer_seeds: &[&[u8]],
+    ) -> Result<(), Error> {
+        sweep_token_account(
+            accounts,
+            token_program,
+            mint,
+            account,
+            destination,
+            authority,
+            signer_seeds,
+        )?;
 
-    #[error("slippage must be within 0 to 100 percent in basis points")]
-    SlippageTooHigh,
-}
+        invoke_signed(
+            &token_cpi::close_account(
+                token_program.key,
+                account.key,
+                rent_destination.key,
+                authority.key,
+            ),
+            accounts,
+            &[signer_seeds],
+        )?;
+
+        Ok(())
+    }
 
-impl Into<ProgramError> for Error {
-    fn into(self) -> ProgramError {
-        match self {
-            Self::ProgramError(err) => err,
-            Self::BorshIoError(err) => ProgramError::BorshIoError(err.to_string()),
-            Self::InsufficientPoolReserve => ProgramError::InvalidInstructionData,
-            Self::SlippageTooHigh => ProgramError::InvalidArgument,
+    /// Pays `keeper` its tip out of a router account and the rest of the lamports to the owner,
+    /// which closes the account.
+    fn close_router_account(
+        account: &AccountInfo,
+        owner: &AccountInfo,
+        keeper: Option<(&AccountInfo, u64)>,
+    ) -> Result<(), Error> {
+        let mut lamports = account.lamports();
+        if let Some((keeper, tip)) = keeper {
+            let mut keeper_lamports = keeper.try_borrow_mut_lamports()?;
+            **keeper_lamports = keeper_lamports
+                .checked_add(tip)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            lamports = lamports
+                .checked_sub(tip)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
         }
+
+        let mut owner_lamports = owner.try_borrow_mut_lamports()?;
+        **owner_lamports = owner_lamports
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        **account.try_borrow_mut_lamports()? = 0;
+        account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
     }
-}
 
-#[cfg(not(feature = "no-entrypoint"))]
-pub mod entrypoint {
-    use borsh::BorshDeserialize;
-    use solana_program::{
-        account_info::{next_account_info, AccountInfo},
-        entrypoint::{entrypoint, ProgramResult},
-        msg,
-        program::invoke,
-        program_error::ProgramError,
-        pubkey::Pubkey,
-    };
+    /// Creates the PumpSwap volume accumulator of a router PDA that buys later on, paid by
+    /// `payer`. PumpSwap `buy` would otherwise create it on the first buy with rent taken from
+    /// the user, which a PDA holding no lamports cannot pay. Accounts are the volume
+    /// accumulator, the PumpSwap event authority and the PumpSwap program.
+    fn init_volume_accumulator<'a, 'info>(
+        accounts: &[AccountInfo<'info>],
+        iter: &mut Iter<'a, AccountInfo<'info>>,
+        payer: &AccountInfo<'info>,
+        user: &AccountInfo<'info>,
+    ) -> Result<(), Error> {
+        let user_volume_accumulator = next_account_info(iter)?;
+        let event_authority = next_account_info(iter)?;
+        let pumpfun_program = next_account_info(iter)?;
 
-    use crate::{pumpfun_cpi, BuyInstruction, Error, Instructions, PoolData, SwapPerformed};
+        if *pumpfun_program.key != PUMPFUN_AMM_PROGRAM {
+            return Err(Error::InvalidPumpfunProgram);
+        }
 
-    entrypoint!(process_instruction);
+        // Anyone may create the accumulator of any user, it may already exist
+        if !user_volume_accumulator.data_is_empty() {
+            return Ok(());
+        }
 
-    pub fn process_instruction(
-        _: &Pubkey,
+        invoke(
+            &pumpfun_cpi::init_user_volume_accumulator(
+                payer.key,
+                user.key,
+                user_volume_accumulator.key,
+                &SYSTEM_PROGRAM,
+                event_authority.key,
+                pumpfun_program.key,
+            ),
+            accounts,
+        )?;
+
+        Ok(())
+    }
+
+    fn place_order(
         accounts: &[AccountInfo],
-        instruction_data: &[u8],
-    ) -> ProgramResult {
-        let instruction = Instructions::try_from_slice(instruction_data)?;
-        match instruction {
-            Instructions::ExecuteSwap(instruction) => {
-                msg!("Instruction: ExecuteSwap");
-                execute_swap(accounts, instruction)
-                    .map_err(|err| <Error as Into<ProgramError>>::into(err))?;
+        instruction: PlaceOrderInstruction,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let order_account = next_account_info(&mut iter)?;
+        let authority = next_account_info(&mut iter)?;
+        let owner = next_account_info(&mut iter)?;
+        let pool_account = next_account_info(&mut iter)?;
+        let input_mint = next_account_info(&mut iter)?;
+        let input_token_program = next_account_info(&mut iter)?;
+        let owner_input_ata = next_account_info(&mut iter)?;
+        let escrow = next_account_info(&mut iter)?;
+        let output_mint = next_account_info(&mut iter)?;
+        let output_token_program = next_account_info(&mut iter)?;
+        let authority_output_ata = next_account_info(&mut iter)?;
+        let system_program = next_account_info(&mut iter)?;
+        let associated_token_program = next_account_info(&mut iter)?;
+
+        if !owner.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        if instruction.limit_price == 0 {
+            return Err(Error::InvalidLimitPrice);
+        }
+
+        if *system_program.key != SYSTEM_PROGRAM {
+            return Err(Error::InvalidSystemProgram);
+        }
+
+        if *associated_token_program.key != ASSOCIATED_TOKEN_PROGRAM {
+            return Err(Error::InvalidAssociatedTokenProgram);
+        }
+
+        check_token_program(input_mint, input_token_program)?;
+        check_token_program(output_mint, output_token_program)?;
+
+        let pool = Pool::load(pool_account)?;
+        let (input, output) = match instruction.direction {
+            Direction::Buy => (pool.quote_mint, pool.base_mint),
+            Direction::Sell => (pool.base_mint, pool.quote_mint),
+        };
+        if *input_mint.key != input || *output_mint.key != output {
+            return Err(Error::MintMismatch);
+        }
+
+        let (order_address, bump) = derive_order(owner.key, instruction.nonce);
+        if *order_account.key != order_address {
+            return Err(Error::InvalidOrderAddress);
+        }
+
+        let (authority_address, authority_bump) = derive_order_authority(order_account.key);
+        if *authority.key != authority_address {
+            return Err(Error::InvalidOrderAuthority);
+        }
+
+        for (ata, mint, token_program) in [
+            (escrow, input_mint, input_token_program),
+            (authority_output_ata, output_mint, output_token_program),
+        ] {
+            let (expected, _) =
+                derive_associated_token_account(authority.key, token_program.key, mint.key);
+            if *ata.key != expected {
+                return Err(Error::InvalidUserAta);
             }
+
+            // The output account exists before the fill so the authority never pays rent
+            invoke(
+                &token_cpi::create_associated_token_account_idempotent(
+                    owner.key,
+                    authority.key,
+                    mint.key,
+                    token_program.key,
+                ),
+                accounts,
+            )?;
         }
 
-        Ok(())
-    }
+        if instruction.direction == Direction::Buy {
+            init_volume_accumulator(accounts, &mut iter, owner, authority)?;
+        }
 
-    pub fn read_pool(accounts: &[AccountInfo]) -> Result<PoolData, Error> {
-        let mut accounts = accounts.iter();
-        let pool = next_account_info(&mut accounts)?;
-        let global_config = next_account_info(&mut accounts)?;
-        let pool_base_ata = next_account_info(&mut accounts)?;
-        let pool_quote_ata = next_account_info(&mut accounts)?;
+        let rent = Rent::get()?.minimum_balance(Order::LEN);
+        invoke_signed(
+            &token_cpi::create_account(
+                owner.key,
+                order_account.key,
+                rent.checked_add(instruction.keeper_tip)
+                    .ok_or(ProgramError::ArithmeticOverflow)?,
+                Order::LEN as u64,
+                &crate::id(),
+            ),
+            accounts,
+            &[&[
+                b"order",
+                owner.key.as_ref(),
+                &instruction.nonce.to_le_bytes(),
+                &[bump],
+            ]],
+        )?;
 
-        PoolData::new(pool, global_config, pool_base_ata, pool_quote_ata)
+        invoke(
+            &token_cpi::transfer_checked(
+                input_token_program.key,
+                owner_input_ata.key,
+                input_mint.key,
+                escrow.key,
+                owner.key,
+                instruction.amount_in,
+                mint_decimals(input_mint)?,
+            ),
+            accounts,
+        )?;
+
+        Order {
+            owner: *owner.key,
+            pool: *pool_account.key,
+            direction: instruction.direction,
+            amount_in: instruction.amount_in,
+            limit_price: instruction.limit_price,
+            expires_at_slot: instruction.expires_at_slot,
+            keeper_tip: instruction.keeper_tip,
+            nonce: instruction.nonce,
+            bump,
+            authority_bump,
+        }
+        .store(order_account)
     }
 
-    fn sub_slippage(amount: u64, slippage_bps: u64) -> Result<u64, ProgramError> {
-        let slippage = ((amount as u128)
-            .checked_mul(slippage_bps as u128)
-            .and_then(|x| x.checked_div(10000u128))
-            .ok_or(ProgramError::ArithmeticOverflow))? as u64;
+    fn fill_order(accounts: &[AccountInfo], config: &RouterConfig) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let order_account = next_account_info(&mut iter)?;
+        let owner = next_account_info(&mut iter)?;
+        let keeper = next_account_info(&mut iter)?;
+        let owner_base_ata = next_account_info(&mut iter)?;
+        let owner_quote_ata = next_account_info(&mut iter)?;
+        let swap_accounts = iter.as_slice();
 
-        let amount = amount
-            .checked_sub(slippage)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
-        Ok(amount)
+        if !keeper.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        let order = Order::load(order_account)?;
+        if *owner.key != order.owner {
+            return Err(Error::InvalidOrderOwner);
+        }
+
+        let slot = Clock::get()?.slot;
+        if slot > order.expires_at_slot {
+            msg!(
+                "Slot {} is past the order expiry {}",
+                slot,
+                order.expires_at_slot
+            );
+            return Err(Error::OrderExpired);
+        }
+
+        let authority_seeds: &[&[u8]] = &[
+            b"order-authority",
+            order_account.key.as_ref(),
+            &[order.authority_bump],
+        ];
+        let authority = Pubkey::create_program_address(authority_seeds, &crate::id())
+            .map_err(|_| Error::InvalidOrderAuthority)?;
+
+        // Peek at the swap accounts, the swap parses them again
+        let swap = SwapAccounts::next(&mut swap_accounts.iter())?;
+        if *swap.pool.key != order.pool {
+            return Err(Error::InvalidOrderPool);
+        }
+
+        if *swap.user.key != authority {
+            return Err(Error::InvalidOrderAuthority);
+        }
+
+        for (ata, user, mint, token_program) in [
+            (
+                owner_base_ata,
+                owner.key,
+                swap.base,
+                swap.base_token_program,
+            ),
+            (
+                owner_quote_ata,
+                owner.key,
+                swap.quote,
+                swap.quote_token_program,
+            ),
+            (
+                swap.user_base_ata,
+                &authority,
+                swap.base,
+                swap.base_token_program,
+            ),
+            (
+                swap.user_quote_ata,
+                &authority,
+                swap.quote,
+                swap.quote_token_program,
+            ),
+        ] {
+            let (expected, _) = derive_associated_token_account(user, token_program.key, mint.key);
+            if *ata.key != expected {
+                return Err(Error::InvalidUserAta);
+            }
+        }
+
+        let pool_state = swap
+            .pool_data()?
+            .with_platform_fee_bps(config.platform_fee_bps);
+        let min_output = order.min_output()?;
+        let output = match order.direction {
+            Direction::Buy => pool_state.base_out(order.amount_in)?,
+            Direction::Sell => pool_state.quote_out(order.amount_in)?,
+        };
+        if output < min_output {
+            msg!("Expected {} out, the limit needs {}", output, min_output);
+            return Err(Error::OrderNotCrossed);
+        }
+
+        let signer_seeds = authority_seeds.iter().map(|seed| seed.to_vec()).collect();
+        match order.direction {
+            Direction::Buy => execute_swap_signed(
+                swap_accounts,
+                config,
+                BuyInstruction::new(order.amount_in, 0, min_output),
+                signer_seeds,
+            )?,
+            Direction::Sell => execute_sell_signed(
+                swap_accounts,
+                config,
+                SellInstruction::new(order.amount_in, 0, min_output),
+                signer_seeds,
+            )?,
+        }
+
+        // The output and whatever input the swap left go to the owner
+        for (ata, owner_ata, mint, token_program) in [
+            (
+                swap.user_base_ata,
+                owner_base_ata,
+                swap.base,
+                swap.base_token_program,
+            ),
+            (
+                swap.user_quote_ata,
+                owner_quote_ata,
+                swap.quote,
+                swap.quote_token_program,
+            ),
+        ] {
+            drain_token_account(
+                accounts,
+                token_program,
+                mint,
+                ata,
+                owner_ata,
+                swap.user,
+                owner,
+                authority_seeds,
+            )?;
+        }
+
+        close_router_account(order_account, owner, Some((keeper, order.keeper_tip)))
     }
 
-    fn execute_swap(accounts: &[AccountInfo], instruction: BuyInstruction) -> Result<(), Error> {
+    fn cancel_order(accounts: &[AccountInfo]) -> Result<(), Error> {
         let mut iter = accounts.iter();
+        let order_account = next_account_info(&mut iter)?;
+        let authority = next_account_info(&mut iter)?;
+        let owner = next_account_info(&mut iter)?;
+        let input_token_program = next_account_info(&mut iter)?;
+        let input_mint = next_account_info(&mut iter)?;
+        let owner_input_ata = next_account_info(&mut iter)?;
+        let escrow = next_account_info(&mut iter)?;
+        let output_token_program = next_account_info(&mut iter)?;
+        let output_mint = next_account_info(&mut iter)?;
+        let owner_output_ata = next_account_info(&mut iter)?;
+        let authority_output_ata = next_account_info(&mut iter)?;
 
-        let pool = next_account_info(&mut iter)?;
-        let user = next_account_info(&mut iter)?;
-        let global_config = next_account_info(&mut iter)?;
-        let base = next_account_info(&mut iter)?;
-        let quote = next_account_info(&mut iter)?;
-        let user_base_ata = next_account_info(&mut iter)?;
-        let user_quote_ata = next_account_info(&mut iter)?;
-        let pool_base_ata = next_account_info(&mut iter)?;
-        let pool_quote_ata = next_account_info(&mut iter)?;
-        let protocol_fee_recipient = next_account_info(&mut iter)?;
-        let protocol_fee_recipient_ata = next_account_info(&mut iter)?;
-        let base_token_program = next_account_info(&mut iter)?;
+        if !owner.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        let order = Order::load(order_account)?;
+        if *owner.key != order.owner {
+            return Err(Error::InvalidOrderOwner);
+        }
+
+        let authority_seeds: &[&[u8]] = &[
+            b"order-authority",
+            order_account.key.as_ref(),
+            &[order.authority_bump],
+        ];
+        let expected_authority = Pubkey::create_program_address(authority_seeds, &crate::id())
+            .map_err(|_| Error::InvalidOrderAuthority)?;
+        if *authority.key != expected_authority {
+            return Err(Error::InvalidOrderAuthority);
+        }
+
+        // Anyone can send tokens to the output account, so it is swept rather than only closed
+        for (ata, mint, token_program, destination) in [
+            (escrow, input_mint, input_token_program, owner_input_ata),
+            (
+                authority_output_ata,
+                output_mint,
+                output_token_program,
+                owner_output_ata,
+            ),
+        ] {
+            check_token_program(mint, token_program)?;
+            let (expected, _) =
+                derive_associated_token_account(authority.key, token_program.key, mint.key);
+            if *ata.key != expected {
+                return Err(Error::InvalidUserAta);
+            }
+
+            drain_token_account(
+                accounts,
+                token_program,
+                mint,
+                ata,
+                destination,
+                authority,
+                owner,
+                authority_seeds,
+            )?;
+        }
+
+        close_router_account(order_account, owner, None)
+    }
+
+    fn create_dca(
+        accounts: &[AccountInfo],
+        instruction: CreateDcaInstruction,
+    ) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let dca_account = next_account_info(&mut iter)?;
+        let authority = next_account_info(&mut iter)?;
+        let owner = next_account_info(&mut iter)?;
+        let pool_account = next_account_info(&mut iter)?;
+        let quote_mint = next_account_info(&mut iter)?;
         let quote_token_program = next_account_info(&mut iter)?;
+        let owner_quote_ata = next_account_info(&mut iter)?;
+        let escrow = next_account_info(&mut iter)?;
+        let base_mint = next_account_info(&mut iter)?;
+        let base_token_program = next_account_info(&mut iter)?;
+        let authority_base_ata = next_account_info(&mut iter)?;
         let system_program = next_account_info(&mut iter)?;
         let associated_token_program = next_account_info(&mut iter)?;
-        let event_authority = next_account_info(&mut iter)?;
-        let pumpfun_program = next_account_info(&mut iter)?;
-        let coin_creator_vault_ata = next_account_info(&mut iter)?;
-        let coint_creator_vault_authority = next_account_info(&mut iter)?;
-        let global_volume_accumulator = next_account_info(&mut iter)?;
-        let user_volume_accumulator = next_account_info(&mut iter)?;
-
-        if !user.is_signer {
-            msg!("Missing user signature");
+
+        if !owner.is_signer {
             return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
         }
 
+        if instruction.amount_per_interval == 0 || instruction.interval_slots == 0 {
+            return Err(Error::InvalidDcaSchedule);
+        }
+
+        if instruction.max_price == 0 {
+            return Err(Error::InvalidLimitPrice);
+        }
+
         if instruction.slippage_bps >= 10_000 {
             msg!("Slippage too high: {}", instruction.slippage_bps);
             return Err(Error::SlippageTooHigh);
         }
 
-        let pool_state = PoolData::new(pool, global_config, pool_base_ata, pool_quote_ata)?;
-        msg!("Pool: {:?}", pool_state);
+        if *system_program.key != SYSTEM_PROGRAM {
+            return Err(Error::InvalidSystemProgram);
+        }
 
-        // 1) Calculate expected base out
-        // 2) Find what slippage is allowed
-        // 3) Remove slippage from the base out
-        let base_out = pool_state.base_out(instruction.input_amount)?;
-        let base_out = sub_slippage(base_out, instruction.slippage_bps)?;
+        if *associated_token_program.key != ASSOCIATED_TOKEN_PROGRAM {
+            return Err(Error::InvalidAssociatedTokenProgram);
+        }
 
-        let buy = pumpfun_cpi::Buy {
-            base_amount_out: base_out,
-            max_quote_amount_in: instruction.input_amount,
-        };
+        check_token_program(quote_mint, quote_token_program)?;
+        check_token_program(base_mint, base_token_program)?;
+
+        let pool = Pool::load(pool_account)?;
+        if *quote_mint.key != pool.quote_mint || *base_mint.key != pool.base_mint {
+            return Err(Error::MintMismatch);
+        }
+
+        let (dca_address, bump) = derive_dca(owner.key, instruction.nonce);
+        if *dca_account.key != dca_address {
+            return Err(Error::InvalidDcaAddress);
+        }
+
+        let (authority_address, authority_bump) = derive_dca_authority(dca_account.key);
+        if *authority.key != authority_address {
+            return Err(Error::InvalidDcaAuthority);
+        }
+
+        for (ata, mint, token_program) in [
+            (escrow, quote_mint, quote_token_program),
+            (authority_base_ata, base_mint, base_token_program),
+        ] {
+            let (expected, _) =
+                derive_associated_token_account(authority.key, token_program.key, mint.key);
+            if *ata.key != expected {
+                return Err(Error::InvalidUserAta);
+            }
+
+            // The base account exists before the first buy so the authority never pays rent
+            invoke(
+                &token_cpi::create_associated_token_account_idempotent(
+                    owner.key,
+                    authority.key,
+                    mint.key,
+                    token_program.key,
+                ),
+                accounts,
+            )?;
+        }
+
+        init_volume_accumulator(accounts, &mut iter, owner, authority)?;
+
+        invoke_signed(
+            &token_cpi::create_account(
+                owner.key,
+                dca_account.key,
+                Rent::get()?.minimum_balance(Dca::LEN),
+                Dca::LEN as u64,
+                &crate::id(),
+            ),
+            accounts,
+            &[&[
+                b"dca",
+                owner.key.as_ref(),
+                &instruction.nonce.to_le_bytes(),
+                &[bump],
+            ]],
+        )?;
+
+        invoke(
+            &token_cpi::transfer_checked(
+                quote_token_program.key,
+                owner_quote_ata.key,
+                quote_mint.key,
+                escrow.key,
+                owner.key,
+                instruction.budget,
+                mint_decimals(quote_mint)?,
+            ),
+            accounts,
+        )?;
+
+        Dca {
+            owner: *owner.key,
+            pool: *pool_account.key,
+            amount_per_interval: instruction.amount_per_interval,
+            interval_slots: instruction.interval_slots,
+            slippage_bps: instruction.slippage_bps,
+            max_price: instruction.max_price,
+            // The first buy can be cranked right away
+            next_fill_slot: Clock::get()?.slot,
+            nonce: instruction.nonce,
+            bump,
+            authority_bump,
+        }
+        .store(dca_account)
+    }
+
+    fn crank_dca(accounts: &[AccountInfo], config: &RouterConfig) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let dca_account = next_account_info(&mut iter)?;
+        let owner = next_account_info(&mut iter)?;
+        let owner_base_ata = next_account_info(&mut iter)?;
+        let swap_accounts = iter.as_slice();
+
+        let mut dca = Dca::load(dca_account)?;
+        if *owner.key != dca.owner {
+            return Err(Error::InvalidDcaOwner);
+        }
+
+        let slot = Clock::get()?.slot;
+        if let Err(err) = dca.advance(slot) {
+            msg!(
+                "Slot {} is before the next DCA buy {}",
+                slot,
+                dca.next_fill_slot
+            );
+            return Err(err);
+        }
+
+        let authority_seeds: &[&[u8]] = &[
+            b"dca-authority",
+            dca_account.key.as_ref(),
+            &[dca.authority_bump],
+        ];
+        let authority = Pubkey::create_program_address(authority_seeds, &crate::id())
+            .map_err(|_| Error::InvalidDcaAuthority)?;
+
+        // Peek at the swap accounts, the swap parses them again
+        let swap = SwapAccounts::next(&mut swap_accounts.iter())?;
+        if *swap.pool.key != dca.pool {
+            return Err(Error::InvalidDcaPool);
+        }
+
+        if *swap.user.key != authority {
+            return Err(Error::InvalidDcaAuthority);
+        }
+
+        for (ata, user, mint, token_program) in [
+            (
+                owner_base_ata,
+                owner.key,
+                swap.base,
+                swap.base_token_program,
+            ),
+            (
+                swap.user_base_ata,
+                &authority,
+                swap.base,
+                swap.base_token_program,
+            ),
+            (
+                swap.user_quote_ata,
+                &authority,
+                swap.quote,
+                swap.quote_token_program,
+            ),
+        ] {
+            let (expected, _) = derive_associated_token_account(user, token_program.key, mint.key);
+            if *ata.key != expected {
+                return Err(Error::InvalidUserAta);
+            }
+        }
 
-        msg!("Buy instruction: {:?}", buy);
-
-        let buy = pumpfun_cpi::buy(
-            pool.key,
-            user.key,
-            global_config.key,
-            base.key,
-            quote.key,
-            user_base_ata.key,
-            user_quote_ata.key,
-            pool_base_ata.key,
-            pool_quote_ata.key,
-            protocol_fee_recipient.key,
-            protocol_fee_recipient_ata.key,
-            base_token_program.key,
-            quote_token_program.key,
-            system_program.key,
-            associated_token_program.key,
-            event_authority.key,
-            pumpfun_program.key,
-            coin_creator_vault_ata.key,
-            coint_creator_vault_authority.key,
-            global_volume_accumulator.key,
-            user_volume_accumulator.key,
-            buy,
+        let amount = dca.next_amount(TokenAccount::new(swap.user_quote_ata)?.amount)?;
+        execute_swap_signed(
+            swap_accounts,
+            config,
+            BuyInstruction::new(amount, dca.slippage_bps, dca.min_output(amount)?),
+            authority_seeds.iter().map(|seed| seed.to_vec()).collect(),
         )?;
 
-        invoke(&buy, accounts)?;
-        msg!(
-            "{:?}",
-            SwapPerformed {
-                input_amount: instruction.input_amount,
-                output_amount: base_out
+        sweep_token_account(
+            accounts,
+            swap.base_token_program,
+            swap.base,
+            swap.user_base_ata,
+            owner_base_ata,
+            swap.user,
+            authority_seeds,
+        )?;
+
+        dca.store(dca_account)
+    }
+
+    fn close_dca(accounts: &[AccountInfo]) -> Result<(), Error> {
+        let mut iter = accounts.iter();
+        let dca_account = next_account_info(&mut iter)?;
+        let authority = next_account_info(&mut iter)?;
+        let owner = next_account_info(&mut iter)?;
+        let quote_token_program = next_account_info(&mut iter)?;
+        let quote_mint = next_account_info(&mut iter)?;
+        let owner_quote_ata = next_account_info(&mut iter)?;
+        let escrow = next_account_info(&mut iter)?;
+        let base_token_program = next_account_info(&mut iter)?;
+        let base_mint = next_account_info(&mut iter)?;
+        let owner_base_ata = next_account_info(&mut iter)?;
+        let authority_base_ata = next_account_info(&mut iter)?;
+
+        if !owner.is_signer {
+            return Err(Error::ProgramError(ProgramError::MissingRequiredSignature));
+        }
+
+        let dca = Dca::load(dca_account)?;
+        if *owner.key != dca.owner {
+            return Err(Error::InvalidDcaOwner);
+        }
+
+        let authority_seeds: &[&[u8]] = &[
+            b"dca-authority",
+            dca_account.key.as_ref(),
+            &[dca.authority_bump],
+        ];
+        let expected_authority = Pubkey::create_program_address(authority_seeds, &crate::id())
+            .map_err(|_| Error::InvalidDcaAuthority)?;
+        if *authority.key != expected_authority {
+            return Err(Error::InvalidDcaAuthority);
+        }
+
+        for (ata, mint, token_program, destination) in [
+            (escrow, quote_mint, quote_token_program, owner_quote_ata),
+            (
+                authority_base_ata,
+                base_mint,
+                base_token_program,
+                owner_base_ata,
+            ),
+        ] {
+            check_token_program(mint, token_program)?;
+            let (expected, _) =
+                derive_associated_token_account(authority.key, token_program.key, mint.key);
+            if *ata.key != expected {
+                return Err(Error::InvalidUserAta);
             }
-        );
+
+            drain_token_account(
+                accounts,
+                token_program,
+                mint,
+                ata,
+                destination,
+                authority,
+                owner,
+                authority_seeds,
+            )?;
+        }
+
+        close_router_account(dca_account, owner, None)
+    }
+
+    fn check_output(output_amount: u64, min_output: u64) -> Result<(), Error> {
+        if output_amount < min_output {
+            msg!(
+                "Received {}, expected at least {}",
+                output_amount,
+                min_output
+            );
+            return Err(Error::InsufficientOutput);
+        }
 
         Ok(())
     }
@@ -0,0 +1,94 @@
+//! Resting limit orders: the router escrows the input of a swap and any keeper executes it
+//! once the pool price crosses the limit.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    ceil_div,
+    config::{load_account, store_account},
+    Direction, Error, PRICE_SCALE,
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub direction: Direction,
+    /// Quote escrowed for a buy, base for a sell.
+    pub amount_in: u64,
+    /// Quote per base scaled by [`PRICE_SCALE`], the most paid on a buy and the least
+    /// received on a sell.
+    pub limit_price: u128,
+    /// Last slot the order can be filled in.
+    pub expires_at_slot: u64,
+    /// Lamports paid to the keeper filling the order, held by the order account.
+    pub keeper_tip: u64,
+    pub nonce: u64,
+    pub bump: u8,
+    pub authority_bump: u8,
+}
+
+impl Order {
+    pub const DISCRIMINATOR: &[u8] = &[134, 173, 223, 185, 77, 86, 28, 51];
+    /// Account size, discriminator included.
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 8 + 16 + 8 + 8 + 8 + 1 + 1;
+
+    pub fn load(account: &AccountInfo) -> Result<Self, Error> {
+        let order: Order = load_account(account, Self::DISCRIMINATOR, Self::LEN)?;
+
+        let expected = Pubkey::create_program_address(
+            &[
+                b"order",
+                order.owner.as_ref(),
+                &order.nonce.to_le_bytes(),
+                &[order.bump],
+            ],
+            &crate::id(),
+        )
+        .map_err(|_| Error::InvalidOrderAddress)?;
+        if *account.key != expected {
+            return Err(Error::InvalidOrderAddress);
+        }
+
+        Ok(order)
+    }
+
+    pub fn store(&self, account: &AccountInfo) -> Result<(), Error> {
+        store_account(self, account, Self::DISCRIMINATOR, Self::LEN)
+    }
+
+    /// Least output filling the whole input at the limit price.
+    pub fn min_output(&self) -> Result<u64, ProgramError> {
+        let (num, denom) = match self.direction {
+            // base >= quote / limit
+            Direction::Buy => (
+                (self.amount_in as u128)
+                    .checked_mul(PRICE_SCALE)
+                    .ok_or(ProgramError::ArithmeticOverflow)?,
+                self.limit_price,
+            ),
+            // quote >= base * limit
+            Direction::Sell => (
+                (self.amount_in as u128)
+                    .checked_mul(self.limit_price)
+                    .ok_or(ProgramError::ArithmeticOverflow)?,
+                PRICE_SCALE,
+            ),
+        };
+
+        u64::try_from(ceil_div(num, denom)?).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+}
+
+pub fn derive_order(owner: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"order", owner.as_ref(), &nonce.to_le_bytes()],
+        &crate::id(),
+    )
+}
+
+/// Owner of the order escrow and the user of the swap filling it.
+pub fn derive_order_authority(order: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"order-authority", order.as_ref()], &crate::id())
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use pumpfun_instructions::return_data::swap_result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer, transaction::Transaction,
+};
+
+async fn transaction(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    instruction: &pumpfun_amm::Instructions,
+    accounts: Vec<solana_instruction::AccountMeta>,
+) -> Result<Transaction, Error> {
+    let mut data = vec![];
+    BorshSerialize::serialize(instruction, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    Ok(Transaction::new_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+    ))
+}
+
+#[tokio::test]
+async fn test_unprofitable_arbitrage() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+
+    // Buying and selling back on the same pool can only lose the fees
+    let mut accounts = common::buy_accounts(&payer.pubkey(), &mint.pubkey());
+    accounts.extend(common::sell_accounts(&payer.pubkey(), &mint.pubkey()));
+
+    let instruction = pumpfun_amm::Instructions::Arbitrage(pumpfun_amm::ArbitrageInstruction::new(
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        0,
+        pumpfun_amm::Venue::Pool,
+        pumpfun_amm::Venue::Pool,
+    ));
+
+    let mut tx = transaction(&rpc, &payer, &instruction, accounts).await?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[payer], recent_blockhash);
+
+    let simulation = rpc.simulate_transaction(&tx).await?.value;
+    assert!(
+        simulation.err.is_some(),
+        "expected the losing cycle to revert"
+    );
+    let expected = format!("Error: {}", pumpfun_amm::Error::UnprofitableArbitrage);
+    assert!(
+        simulation
+            .logs
+            .unwrap_or_default()
+            .iter()
+            .any(|log| log.ends_with(&expected)),
+        "expected the cycle to revert as unprofitable"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_profitable_arbitrage() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    // Doubling the pool quote reserve lifts the pool price well above the curve price
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let buy = pumpfun_amm::Instructions::ExecuteSwap(pumpfun_amm::BuyInstruction::new(
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        100, // 1%
+        1,
+    ));
+    let mut tx = transaction(
+        &rpc,
+        &payer,
+        &buy,
+        common::buy_accounts(&payer.pubkey(), &mint.pubkey()),
+    )
+    .await?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    rpc.send_and_confirm_transaction(&tx).await?;
+
+    let input_amount = (0.001 * LAMPORTS_PER_SOL as f64) as u64;
+    let mut accounts = common::curve_buy_accounts(&payer.pubkey(), &mint.pubkey());
+    accounts.extend(common::sell_accounts(&payer.pubkey(), &mint.pubkey()));
+
+    let instruction = pumpfun_amm::Instructions::Arbitrage(pumpfun_amm::ArbitrageInstruction::new(
+        input_amount,
+        1,
+        pumpfun_amm::Venue::Curve,
+        pumpfun_amm::Venue::Pool,
+    ));
+
+    let mut tx = transaction(&rpc, &payer, &instruction, accounts).await?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+
+    let simulation = rpc.simulate_transaction(&tx).await?.value;
+    assert!(simulation.err.is_none(), "{:?}", simulation.logs);
+    let result = swap_result(&simulation)?.expect("missing swap result");
+    assert_eq!(result.input_amount, input_amount);
+    assert!(
+        result.output_amount > input_amount,
+        "expected the cycle to return more quote than it spent: {:?}",
+        result
+    );
+
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+    println!("Arbitrage successful: {}", sig);
+
+    Ok(())
+}
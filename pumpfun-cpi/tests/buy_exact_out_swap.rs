@@ -0,0 +1,62 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+
+#[tokio::test]
+async fn test_buy_exact_out_swap() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let accounts = common::buy_accounts(&payer.pubkey(), &mint.pubkey());
+
+    assert!(accounts.len() == 21, "{}", accounts.len());
+    let base_amount_out = 1_000_000;
+    let instruction = pumpfun_amm::Instructions::BuyExactOut(
+        pumpfun_amm::BuyExactOutInstruction::new(base_amount_out, 100), // 1%
+    );
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&instruction, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let payer_pubkey = payer.pubkey();
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Exact out swap execution successful: {}", sig);
+
+    let mint_ata =
+        spl_associated_token_account::get_associated_token_address(&payer_pubkey, &mint.pubkey());
+
+    // Mint balance is 0 as all mint tokens are deposisted into the pool on setup
+    let balance = rpc.get_token_account_balance(&mint_ata).await?;
+    assert!(
+        balance.amount.parse::<u64>()? >= base_amount_out,
+        "expected mint ATA balance to be at least {}",
+        base_amount_out
+    );
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use std::{
     env,
     fs::{self, File},
@@ -5,16 +7,25 @@ use std::{
 };
 
 use anyhow::Error;
-use borsh::BorshDeserialize;
-use pumpfun_global::{derive_bounding_curve, derive_pool};
-use pumpfun_instructions::launchpad::{buy, create_token, Buy, CreateToken};
+use borsh::{BorshDeserialize, BorshSerialize};
+use pumpfun_amm::config::{derive_config, RouterConfig};
+use pumpfun_global::{
+    derive_amm_user_volume_accumulator, derive_bounding_curve, derive_coin_creator_vault_authority,
+    derive_pool, PUMPFUN_AMM_PROGRAM, PUMP_FUN_AMM_EVENT_AUTHORITY, PUMP_FUN_AMM_FEE_RECIPIENT,
+    PUMP_FUN_AMM_GLOBAL_VOLUME_ACCUMULATOR, PUMP_FUN_GLOBAL_CONFIG,
+};
+use pumpfun_instructions::launchpad::{buy, create_token, sell, Buy, CreateToken, Sell};
 use pumpfun_instructions::{
     amm::{create_pool, CreatePool},
     launchpad::BoundingCurve,
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_instruction::AccountMeta;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
-use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction};
+use solana_sdk::{
+    account_info::AccountInfo, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
 use tracing::{info, warn};
 
 pub static DEV_NET: &str = "https://api.devnet.solana.com";
@@ -32,21 +43,25 @@ pub fn read_or_persist_keypair(path: impl AsRef<Path>) -> Result<Keypair, Error>
             fs::create_dir_all(parent)?;
         }
 
-        let fd = File::options().create(true).write(true).open(&path)?;
+        let fd = File::options()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)?;
         serde_json::to_writer(fd, keypair.to_bytes().as_ref())?;
         return Ok(keypair);
     }
 
     let bytes = fs::read(path)?;
     let keypair: Vec<u8> = serde_json::from_slice(&bytes)?;
-    let keypair = Keypair::from_bytes(&keypair)?;
+    let keypair = Keypair::try_from(keypair.as_slice())?;
 
     Ok(keypair)
 }
 
 pub async fn read_bounding_curve(rpc: &RpcClient, mint: &Pubkey) -> Result<BoundingCurve, Error> {
     let (bounding_curve, _) = derive_bounding_curve(mint);
-    let mut bounding_curve = rpc.get_account_data(&bounding_curve).await?;
+    let bounding_curve = rpc.get_account_data(&bounding_curve).await?;
     Ok(BorshDeserialize::deserialize(&mut bounding_curve.as_ref())?)
 }
 
@@ -82,12 +97,8 @@ async fn _setup(
             &spl_token::native_mint::id(),
             &spl_token::id(),
         ),
-        solana_sdk::system_instruction::transfer(
-            &payer.pubkey(),
-            &user_quote_ata,
-            lamports_in_pool,
-        ),
-        spl_token::instruction::sync_native(&spl_token::id(), &user_quote_ata)?,
+        solana_sdk::system_instruction::transfer(&payer.pubkey(), user_quote_ata, lamports_in_pool),
+        spl_token::instruction::sync_native(&spl_token::id(), user_quote_ata)?,
     ];
 
     let recent_blockhash = rpc.get_latest_blockhash().await?;
@@ -106,11 +117,11 @@ async fn _setup(
     let pool_instructions = vec![
         buy(
             &payer.pubkey(),
-            &user_base_ata,
+            user_base_ata,
             &mint.pubkey(),
             &payer.pubkey(),
             Buy {
-                amount: amount,
+                amount,
                 max_sol_cost: lamports_in_pool,
             },
         ),
@@ -118,8 +129,8 @@ async fn _setup(
             &payer.pubkey(),
             &mint.pubkey(),                // Base
             &spl_token::native_mint::id(), // Quote
-            &user_base_ata,
-            &user_quote_ata,
+            user_base_ata,
+            user_quote_ata,
             CreatePool {
                 index: 0,
                 coin_creator: payer.pubkey(),
@@ -223,6 +234,270 @@ pub async fn transfer_wsol(rpc: &RpcClient, payer: &Keypair, lamports: u64) -> R
     Ok(())
 }
 
+/// Appends the fee vault and the router config every swap takes last, the fee vault being the
+/// WSOL account of the fee destination of the deployed router.
+pub async fn with_config(
+    rpc: &RpcClient,
+    mut accounts: Vec<AccountMeta>,
+) -> Result<Vec<AccountMeta>, Error> {
+    let (config, _) = derive_config();
+    let data = rpc.get_account_data(&config).await?;
+    let router_config = RouterConfig::try_from_slice(&data[8..RouterConfig::LEN])?;
+
+    accounts.extend([
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(
+                &router_config.fee_destination,
+                &spl_token::native_mint::id(),
+            ),
+            false,
+        ),
+        AccountMeta::new_readonly(config, false),
+    ]);
+
+    Ok(accounts)
+}
+
+/// Accounts of the router `ExecuteSell` instruction for the pool created by [`setup`].
+pub fn sell_accounts(payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    swap_accounts(AccountMeta::new(*payer, true), payer, mint)
+}
+
+/// Accounts of the router `ExecuteSell` instruction for the pool created by [`setup`] with a
+/// program derived `authority` as the user.
+pub fn authority_sell_accounts(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+) -> Vec<AccountMeta> {
+    swap_accounts(AccountMeta::new(*authority, false), payer, mint)
+}
+
+fn swap_accounts(user: AccountMeta, payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let (pool, _) = derive_pool(0, payer, mint, &spl_token::native_mint::id());
+    // `setup` creates the pool with the payer as the coin creator
+    let (coin_creator_vault_authority, _) = derive_coin_creator_vault_authority(payer);
+    let user_key = user.pubkey;
+
+    vec![
+        AccountMeta::new_readonly(pool, false),
+        user,
+        AccountMeta::new_readonly(PUMP_FUN_GLOBAL_CONFIG, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(&user_key, mint),
+            false,
+        ),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(
+                &user_key,
+                &spl_token::native_mint::id(),
+            ),
+            false,
+        ),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(&pool, mint),
+            false,
+        ),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(
+                &pool,
+                &spl_token::native_mint::id(),
+            ),
+            false,
+        ),
+        AccountMeta::new_readonly(PUMP_FUN_AMM_FEE_RECIPIENT, false),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(
+                &PUMP_FUN_AMM_FEE_RECIPIENT,
+                &spl_token::native_mint::id(),
+            ),
+            false,
+        ),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(PUMP_FUN_AMM_EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(PUMPFUN_AMM_PROGRAM, false),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(
+                &coin_creator_vault_authority,
+                &spl_token::native_mint::id(),
+            ),
+            false,
+        ),
+        AccountMeta::new_readonly(coin_creator_vault_authority, false),
+    ]
+}
+
+/// Accounts of the router `ExecuteSwap` instruction for the pool created by [`setup`].
+pub fn buy_accounts(payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    with_volume_accumulators(sell_accounts(payer, mint), payer)
+}
+
+/// Accounts of the router `ExecuteSwap` instruction for the pool created by [`setup`] with a
+/// program derived `authority` as the user.
+pub fn authority_buy_accounts(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+) -> Vec<AccountMeta> {
+    with_volume_accumulators(authority_sell_accounts(authority, payer, mint), authority)
+}
+
+/// Accounts `PlaceOrder` and `CreateDca` take to create the PumpSwap volume accumulator of a
+/// router `authority` before its first buy.
+pub fn volume_accumulator_accounts(authority: &Pubkey) -> Vec<AccountMeta> {
+    let (user_volume_accumulator, _) = derive_amm_user_volume_accumulator(authority);
+
+    vec![
+        AccountMeta::new(user_volume_accumulator, false),
+        AccountMeta::new_readonly(PUMP_FUN_AMM_EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(PUMPFUN_AMM_PROGRAM, false),
+    ]
+}
+
+fn with_volume_accumulators(mut accounts: Vec<AccountMeta>, user: &Pubkey) -> Vec<AccountMeta> {
+    let (user_volume_accumulator, _) = derive_amm_user_volume_accumulator(user);
+    accounts.extend([
+        AccountMeta::new(PUMP_FUN_AMM_GLOBAL_VOLUME_ACCUMULATOR, false),
+        AccountMeta::new(user_volume_accumulator, false),
+    ]);
+
+    accounts
+}
+
+/// Accounts of the router `CurveBuy` instruction for the token created by [`setup`], the
+/// launchpad `buy` accounts followed by the associated token program.
+pub fn curve_buy_accounts(payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let user_base_ata = spl_associated_token_account::get_associated_token_address(payer, mint);
+    let buy = buy(
+        payer,
+        &user_base_ata,
+        mint,
+        payer,
+        Buy {
+            amount: 0,
+            max_sol_cost: 0,
+        },
+    );
+
+    let mut accounts = buy.accounts;
+    accounts.push(AccountMeta::new_readonly(
+        spl_associated_token_account::id(),
+        false,
+    ));
+
+    accounts
+}
+
+/// Accounts of the router `CurveSell` instruction for the token created by [`setup`], the
+/// launchpad `sell` accounts.
+pub fn curve_sell_accounts(payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let user_base_ata = spl_associated_token_account::get_associated_token_address(payer, mint);
+    let sell = sell(
+        payer,
+        &user_base_ata,
+        mint,
+        payer,
+        Sell {
+            amount: 0,
+            min_sol_output: 0,
+        },
+    );
+
+    sell.accounts
+}
+
+/// Accounts of the router `SmartBuy` instruction for the token created by [`setup`].
+pub fn smart_buy_accounts(payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts = curve_buy_accounts(payer, mint);
+    accounts.extend(buy_accounts(payer, mint));
+
+    accounts
+}
+
+/// Accounts of the router `SmartSell` instruction for the token created by [`setup`].
+pub fn smart_sell_accounts(payer: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts = curve_sell_accounts(payer, mint);
+    accounts.extend(sell_accounts(payer, mint));
+
+    accounts
+}
+
+/// Account held by an offline test and lent to the router as an [`AccountInfo`].
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        TestAccount {
+            key,
+            owner,
+            lamports: 0,
+            data,
+        }
+    }
+
+    /// Zeroed account of `len` bytes owned by the router, for the state it stores itself.
+    pub fn router(key: Pubkey, len: usize) -> Self {
+        TestAccount::new(key, pumpfun_amm::id(), vec![0; len])
+    }
+
+    /// Anchor account of the type identified by `discriminator` holding `value`, zero padded to
+    /// `len` bytes.
+    pub fn anchor(
+        key: Pubkey,
+        owner: Pubkey,
+        discriminator: &[u8],
+        value: &impl BorshSerialize,
+        len: usize,
+    ) -> Self {
+        let mut data = discriminator.to_vec();
+        data.extend(borsh::to_vec(value).expect("serializable account"));
+        data.resize(len.max(data.len()), 0);
+
+        TestAccount::new(key, owner, data)
+    }
+
+    /// SPL Token account of `mint` holding `amount`.
+    pub fn token(key: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Self {
+        let mut data = vec![0; 165];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+
+        TestAccount::new(key, spl_token::id(), data)
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            false,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+
+    /// [`TestAccount::info`] signing the transaction.
+    pub fn signer(&mut self) -> AccountInfo<'_> {
+        AccountInfo {
+            is_signer: true,
+            ..self.info()
+        }
+    }
+}
+
 pub fn setup_logger() {
     tracing_subscriber::fmt()
         .pretty()
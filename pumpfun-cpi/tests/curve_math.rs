@@ -0,0 +1,116 @@
+use pumpfun_amm::{
+    launchpad::{BoundingCurve, CurveData, Global},
+    Fees,
+};
+use solana_sdk::pubkey::Pubkey;
+
+// Reserves of a freshly created token, the vectors follow the launchpad integer arithmetic:
+// buy cost rounded up by one atom, fees rounded up per component, sell output rounded down.
+fn curve_data(creator: Pubkey) -> CurveData {
+    CurveData {
+        curve: BoundingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator,
+        },
+        global: Global {
+            initialized: true,
+            authority: Pubkey::default(),
+            fee_recipient: Pubkey::new_unique(),
+            initial_virtual_token_reserves: 1_073_000_000_000_000,
+            initial_virtual_sol_reserves: 30_000_000_000,
+            initial_real_token_reserves: 793_100_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            fee_basis_points: 95,
+            withdraw_authority: Pubkey::default(),
+            enable_migrate: true,
+            pool_migration_fee: 0,
+            creator_fee_basis_points: 5,
+            fee_recipients: [Pubkey::default(); 7],
+            set_creator_authority: Pubkey::default(),
+        },
+    }
+}
+
+#[test]
+fn test_curve_buy_reference_vectors() {
+    let with_creator = curve_data(Pubkey::new_unique());
+    let without_creator = curve_data(Pubkey::default());
+
+    // (curve, sol_amount_in, base_amount_out, protocol_fee, coin_creator_fee)
+    let vectors = [
+        (
+            &with_creator,
+            1_000_000_000,
+            34_281_150_129_545,
+            9_405_941,
+            495_050,
+        ),
+        (&with_creator, 12_345, 437_104_255, 117, 7),
+        (
+            &without_creator,
+            1_000_000_000,
+            34_297_586_679_651,
+            9_410_600,
+            0,
+        ),
+        (&without_creator, 12_345, 437_354_621, 117, 0),
+    ];
+
+    for (curve, sol_in, base_out, protocol_fee, coin_creator_fee) in vectors {
+        let fees = Fees {
+            lp_fee: 0,
+            protocol_fee,
+            coin_creator_fee,
+        };
+        assert_eq!(curve.base_out(sol_in).unwrap(), base_out);
+        assert_eq!(curve.quote_in(base_out).unwrap(), (sol_in, fees));
+    }
+}
+
+#[test]
+fn test_curve_sell_reference_vectors() {
+    let with_creator = curve_data(Pubkey::new_unique());
+    let without_creator = curve_data(Pubkey::default());
+
+    // (curve, base_amount_in, sol_amount_out, protocol_fee, coin_creator_fee)
+    let vectors = [
+        (
+            &with_creator,
+            1_000_000_000_000,
+            27_653_629,
+            265_364,
+            13_967,
+        ),
+        (&without_creator, 1_000_000_000_000, 27_667_596, 265_364, 0),
+    ];
+
+    for (curve, base_in, sol_out, protocol_fee, coin_creator_fee) in vectors {
+        let fees = Fees {
+            lp_fee: 0,
+            protocol_fee,
+            coin_creator_fee,
+        };
+        assert_eq!(curve.quote_out(base_in).unwrap(), sol_out);
+        assert_eq!(
+            curve
+                .fees(curve.quote_out_before_fees(base_in).unwrap())
+                .unwrap(),
+            fees
+        );
+    }
+}
+
+#[test]
+fn test_curve_buy_capped_by_real_reserves() {
+    let curve = curve_data(Pubkey::new_unique());
+
+    assert_eq!(
+        curve.base_out(1_000 * 1_000_000_000).unwrap(),
+        curve.curve.real_token_reserves
+    );
+}
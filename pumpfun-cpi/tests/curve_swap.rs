@@ -0,0 +1,101 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+
+#[tokio::test]
+async fn test_curve_swap() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    let mint_ata =
+        spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint.pubkey());
+    let base_before = rpc
+        .get_token_account_balance(&mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+
+    let accounts = common::curve_buy_accounts(&payer.pubkey(), &mint.pubkey());
+    assert!(accounts.len() == 15, "{}", accounts.len());
+    let buy = pumpfun_amm::Instructions::CurveBuy(pumpfun_amm::CurveBuyInstruction::new(
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        100, // 1%
+        1,
+    ));
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&buy, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Curve buy successful: {}", sig);
+
+    let base_after = rpc
+        .get_token_account_balance(&mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    assert!(
+        base_after > base_before,
+        "expected mint ATA balance to increase"
+    );
+
+    let accounts = common::curve_sell_accounts(&payer.pubkey(), &mint.pubkey());
+    assert!(accounts.len() == 12, "{}", accounts.len());
+    let sell = pumpfun_amm::Instructions::CurveSell(pumpfun_amm::CurveSellInstruction::new(
+        base_after - base_before,
+        100, // 1%
+        1,
+    ));
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&sell, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Curve sell successful: {}", sig);
+
+    let balance = rpc
+        .get_token_account_balance(&mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    assert_eq!(
+        balance, base_before,
+        "expected the bought tokens to be sold"
+    );
+
+    Ok(())
+}
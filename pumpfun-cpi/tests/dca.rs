@@ -0,0 +1,88 @@
+mod common;
+
+use common::TestAccount;
+use pumpfun_amm::{
+    dca::{derive_dca, derive_dca_authority, Dca},
+    Error, PRICE_SCALE,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn dca(amount_per_interval: u64, interval_slots: u64) -> Dca {
+    let owner = Pubkey::new_unique();
+    let (dca, bump) = derive_dca(&owner, 3);
+    Dca {
+        owner,
+        pool: Pubkey::new_unique(),
+        amount_per_interval,
+        interval_slots,
+        slippage_bps: 100,
+        max_price: 2 * PRICE_SCALE,
+        next_fill_slot: 1_000,
+        nonce: 3,
+        bump,
+        authority_bump: derive_dca_authority(&dca).1,
+    }
+}
+
+#[test]
+fn test_dca_advance_at_slot_boundaries() {
+    let mut dca = dca(100, 50);
+    assert!(matches!(dca.advance(999), Err(Error::DcaNotDue)));
+    assert_eq!(dca.next_fill_slot, 1_000);
+
+    // Due on the scheduled slot itself
+    dca.advance(1_000).unwrap();
+    assert_eq!(dca.next_fill_slot, 1_050);
+    assert!(matches!(dca.advance(1_049), Err(Error::DcaNotDue)));
+
+    // A late crank schedules the next buy from its own slot, not from the missed one
+    dca.advance(1_120).unwrap();
+    assert_eq!(dca.next_fill_slot, 1_170);
+
+    // The schedule saturates instead of wrapping around
+    dca.advance(u64::MAX - 10).unwrap();
+    assert_eq!(dca.next_fill_slot, u64::MAX);
+}
+
+#[test]
+fn test_dca_next_amount_on_final_interval() {
+    // A budget of 250 buys 100, 100, then the 50 left
+    let dca = dca(100, 50);
+    assert_eq!(dca.next_amount(250).unwrap(), 100);
+    assert_eq!(dca.next_amount(150).unwrap(), 100);
+    assert_eq!(dca.next_amount(50).unwrap(), 50);
+    assert!(matches!(dca.next_amount(0), Err(Error::DcaCompleted)));
+
+    // An exact multiple ends on a full interval
+    assert_eq!(dca.next_amount(100).unwrap(), 100);
+}
+
+#[test]
+fn test_dca_min_output() {
+    // Buying with 100 quote at no more than 2 quote per base needs at least 50 base
+    let dca = dca(100, 50);
+    assert_eq!(dca.min_output(100).unwrap(), 50);
+
+    // Rounded up so no buy beats the max price, 50.5 and 49.5 base need 51 and 50
+    assert_eq!(dca.min_output(101).unwrap(), 51);
+    assert_eq!(dca.min_output(99).unwrap(), 50);
+
+    // The smaller final buy is bounded at the same price
+    assert_eq!(dca.min_output(dca.next_amount(51).unwrap()).unwrap(), 26);
+}
+
+#[test]
+fn test_dca_rejects_copy_at_other_address() {
+    let dca = dca(100, 50);
+    let (key, _) = derive_dca(&dca.owner, dca.nonce);
+    let mut account = TestAccount::router(key, Dca::LEN);
+    dca.store(&account.info()).unwrap();
+    assert_eq!(Dca::load(&account.info()).unwrap(), dca);
+
+    // The same bytes in another router account must not pass for the DCA
+    account.key = Pubkey::new_unique();
+    assert!(matches!(
+        Dca::load(&account.info()),
+        Err(Error::InvalidDcaAddress)
+    ));
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use pumpfun_amm::{
+    dca::{derive_dca, derive_dca_authority},
+    PRICE_SCALE,
+};
+use pumpfun_global::{derive_amm_user_volume_accumulator, derive_pool};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+
+fn transaction(
+    payer: &Keypair,
+    instruction: &pumpfun_amm::Instructions,
+    accounts: Vec<AccountMeta>,
+) -> Result<Transaction, Error> {
+    let mut data = vec![];
+    BorshSerialize::serialize(instruction, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    Ok(Transaction::new_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+    ))
+}
+
+async fn send(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    instruction: &pumpfun_amm::Instructions,
+    accounts: Vec<AccountMeta>,
+) -> Result<(), Error> {
+    let mut tx = transaction(payer, instruction, accounts)?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+    println!("{:?} successful: {}", instruction, sig);
+
+    Ok(())
+}
+
+async fn balance(rpc: &RpcClient, ata: &Pubkey) -> Result<u64, Error> {
+    Ok(rpc.get_token_account_balance(ata).await?.amount.parse()?)
+}
+
+fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(owner, mint)
+}
+
+#[tokio::test]
+async fn test_dca_flow() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let owner = payer.pubkey();
+    let mint = mint.pubkey();
+    let wsol = spl_token::native_mint::id();
+    let amount_per_interval = (0.001 * LAMPORTS_PER_SOL as f64) as u64;
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let (dca, _) = derive_dca(&owner, nonce);
+    let (authority, _) = derive_dca_authority(&dca);
+    let (pool, _) = derive_pool(0, &owner, &mint, &wsol);
+
+    // Two buys, the second never due during the test. Paying up to one quote per base is far
+    // above the pool price, so the first buy stays within the max price
+    let instruction = pumpfun_amm::Instructions::CreateDca(pumpfun_amm::CreateDcaInstruction::new(
+        nonce,
+        2 * amount_per_interval,
+        amount_per_interval,
+        1_000_000,
+        100, // 1%
+        PRICE_SCALE,
+    ));
+    let mut accounts = vec![
+        AccountMeta::new(dca, false),
+        AccountMeta::new_readonly(authority, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(pool, false),
+        AccountMeta::new_readonly(wsol, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(ata(&owner, &wsol), false),
+        AccountMeta::new(ata(&authority, &wsol), false),
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(ata(&authority, &mint), false),
+        AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+    accounts.extend(common::volume_accumulator_accounts(&authority));
+    send(&rpc, &payer, &instruction, accounts).await?;
+
+    // The first crank buys with an authority that never traded and holds no lamports
+    assert_eq!(rpc.get_balance(&authority).await?, 0);
+    let (user_volume_accumulator, _) = derive_amm_user_volume_accumulator(&authority);
+    assert!(rpc.get_account(&user_volume_accumulator).await.is_ok());
+
+    let mut crank_accounts = vec![
+        AccountMeta::new(dca, false),
+        AccountMeta::new_readonly(owner, false),
+        AccountMeta::new(ata(&owner, &mint), false),
+    ];
+    crank_accounts.extend(common::authority_buy_accounts(&authority, &owner, &mint));
+    let crank_accounts = common::with_config(&rpc, crank_accounts).await?;
+
+    let base_before = balance(&rpc, &ata(&owner, &mint)).await?;
+    send(
+        &rpc,
+        &payer,
+        &pumpfun_amm::Instructions::CrankDca,
+        crank_accounts.clone(),
+    )
+    .await?;
+
+    assert!(balance(&rpc, &ata(&owner, &mint)).await? > base_before);
+    assert_eq!(
+        balance(&rpc, &ata(&authority, &wsol)).await?,
+        amount_per_interval
+    );
+
+    let mut tx = transaction(&payer, &pumpfun_amm::Instructions::CrankDca, crank_accounts)?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let simulation = rpc.simulate_transaction(&tx).await?.value;
+    let expected = format!("Error: {}", pumpfun_amm::Error::DcaNotDue);
+    assert!(
+        simulation
+            .logs
+            .unwrap_or_default()
+            .iter()
+            .any(|log| log.ends_with(&expected)),
+        "expected the second buy not due yet"
+    );
+
+    let quote_before = balance(&rpc, &ata(&owner, &wsol)).await?;
+    let accounts = vec![
+        AccountMeta::new(dca, false),
+        AccountMeta::new_readonly(authority, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(wsol, false),
+        AccountMeta::new(ata(&owner, &wsol), false),
+        AccountMeta::new(ata(&authority, &wsol), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new(ata(&owner, &mint), false),
+        AccountMeta::new(ata(&authority, &mint), false),
+    ];
+    send(&rpc, &payer, &pumpfun_amm::Instructions::CloseDca, accounts).await?;
+
+    assert!(
+        rpc.get_account(&dca).await.is_err(),
+        "expected the DCA closed"
+    );
+    assert_eq!(
+        balance(&rpc, &ata(&owner, &wsol)).await?,
+        quote_before + amount_per_interval
+    );
+
+    Ok(())
+}
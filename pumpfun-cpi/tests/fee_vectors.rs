@@ -0,0 +1,237 @@
+mod common;
+
+use std::collections::HashMap;
+
+use anyhow::Error;
+use borsh::BorshDeserialize;
+use common::TestData;
+use pumpfun_amm::{Fees, Pool, PoolData, PoolGlobalConfig};
+use pumpfun_global::{
+    derive_coin_creator_vault_authority, derive_global_config, derive_pool, PUMPFUN_AMM_PROGRAM,
+    PUMP_FUN_AMM_FEE_RECIPIENT,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signature, signer::Signer,
+    transaction::Transaction,
+};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use tracing::info;
+
+const BUY_DISCRIMINATOR: &[u8] = &[102, 6, 61, 18, 1, 218, 235, 234];
+const SELL_DISCRIMINATOR: &[u8] = &[51, 230, 133, 164, 1, 127, 131, 173];
+
+/// Token accounts moved by a PumpSwap swap on the pool created by [`common::setup`].
+struct SwapAccounts {
+    user_quote_ata: Pubkey,
+    pool_base_ata: Pubkey,
+    pool_quote_ata: Pubkey,
+    protocol_fee_recipient_ata: Pubkey,
+    coin_creator_vault_ata: Pubkey,
+}
+
+impl SwapAccounts {
+    fn new(payer: &Pubkey, mint: &Pubkey) -> Self {
+        let wsol = spl_token::native_mint::id();
+        let (pool, _) = derive_pool(0, payer, mint, &wsol);
+        let (coin_creator_vault_authority, _) = derive_coin_creator_vault_authority(payer);
+        let ata = spl_associated_token_account::get_associated_token_address;
+
+        SwapAccounts {
+            user_quote_ata: ata(payer, &wsol),
+            pool_base_ata: ata(&pool, mint),
+            pool_quote_ata: ata(&pool, &wsol),
+            protocol_fee_recipient_ata: ata(&PUMP_FUN_AMM_FEE_RECIPIENT, &wsol),
+            coin_creator_vault_ata: ata(&coin_creator_vault_authority, &wsol),
+        }
+    }
+}
+
+/// Token balances of one confirmed transaction before and after it, by token account.
+struct Balances {
+    pre: HashMap<Pubkey, u64>,
+    post: HashMap<Pubkey, u64>,
+}
+
+impl Balances {
+    async fn recorded(rpc: &RpcClient, tx: &Transaction, sig: &Signature) -> Result<Self, Error> {
+        let meta = rpc
+            .get_transaction(sig, UiTransactionEncoding::Json)
+            .await?
+            .transaction
+            .meta
+            .ok_or_else(|| anyhow::anyhow!("missing transaction meta"))?;
+
+        let by_account = |balances: OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+            Option::<Vec<_>>::from(balances)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|balance| {
+                    let key = tx.message.account_keys[balance.account_index as usize];
+                    (key, balance.ui_token_amount.amount.parse().unwrap())
+                })
+                .collect()
+        };
+
+        Ok(Balances {
+            pre: by_account(meta.pre_token_balances),
+            post: by_account(meta.post_token_balances),
+        })
+    }
+
+    fn pre(&self, account: &Pubkey) -> u64 {
+        self.pre.get(account).copied().unwrap_or_default()
+    }
+
+    fn delta(&self, account: &Pubkey) -> i128 {
+        let post = self.post.get(account).copied().unwrap_or_default();
+        post as i128 - self.pre(account) as i128
+    }
+}
+
+/// Pool state before the recorded swap, reserves taken from the same transaction.
+async fn pool_data(
+    rpc: &RpcClient,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    accounts: &SwapAccounts,
+    balances: &Balances,
+) -> Result<PoolData, Error> {
+    let (pool, _) = derive_pool(0, payer, mint, &spl_token::native_mint::id());
+    let pool = rpc.get_account_data(&pool).await?;
+    let (global_config, _) = derive_global_config();
+    let global_config = rpc.get_account_data(&global_config).await?;
+
+    Ok(PoolData {
+        reserve_base: balances.pre(&accounts.pool_base_ata),
+        reserve_quote: balances.pre(&accounts.pool_quote_ata),
+        global_config: PoolGlobalConfig::try_from_slice(&global_config[8..353])?,
+        pool: Pool::try_from_slice(&pool[8..243])?,
+        platform_fee_bps: 0,
+    })
+}
+
+async fn send(
+    rpc: &RpcClient,
+    payer: &solana_sdk::signature::Keypair,
+    instruction: Instruction,
+) -> Result<(Transaction, Signature), Error> {
+    let mut tx = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    Ok((tx, sig))
+}
+
+/// Checks the quote a PumpSwap swap moved against the fees `PoolData` expects, the pool keeps
+/// the lp fee while the protocol and coin creator fees leave it.
+fn check_fees(accounts: &SwapAccounts, balances: &Balances, fees: &Fees) {
+    assert_eq!(
+        balances.delta(&accounts.protocol_fee_recipient_ata),
+        fees.protocol_fee as i128
+    );
+    assert_eq!(
+        balances.delta(&accounts.coin_creator_vault_ata),
+        fees.coin_creator_fee as i128
+    );
+}
+
+#[tokio::test]
+async fn test_fee_vectors_match_pumpswap() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let accounts = SwapAccounts::new(&payer.pubkey(), &mint.pubkey());
+
+    // Buy straight on PumpSwap so the recorded balances carry no router logic
+    let base_amount_out: u64 = 1_000_000_000;
+    let mut data = BUY_DISCRIMINATOR.to_vec();
+    data.extend(base_amount_out.to_le_bytes());
+    data.extend(((0.005 * LAMPORTS_PER_SOL as f64) as u64).to_le_bytes());
+    let (tx, sig) = send(
+        &rpc,
+        &payer,
+        Instruction {
+            data,
+            accounts: common::buy_accounts(&payer.pubkey(), &mint.pubkey()),
+            program_id: PUMPFUN_AMM_PROGRAM,
+        },
+    )
+    .await?;
+
+    let balances = Balances::recorded(&rpc, &tx, &sig).await?;
+    let pool = pool_data(&rpc, &payer.pubkey(), &mint.pubkey(), &accounts, &balances).await?;
+    let (quote_amount_in, fees) = pool.quote_in(base_amount_out)?;
+    info!(
+        "Buy {}: reserves {}/{}, base out {}, quote in {}, {:?}",
+        sig, pool.reserve_base, pool.reserve_quote, base_amount_out, quote_amount_in, fees
+    );
+
+    assert_eq!(
+        balances.delta(&accounts.pool_base_ata),
+        -(base_amount_out as i128)
+    );
+    assert_eq!(
+        balances.delta(&accounts.user_quote_ata),
+        -(quote_amount_in as i128)
+    );
+    assert_eq!(
+        balances.delta(&accounts.pool_quote_ata),
+        (quote_amount_in - fees.protocol_fee - fees.coin_creator_fee) as i128
+    );
+    check_fees(&accounts, &balances, &fees);
+
+    // Sell the bought base back
+    let mut data = SELL_DISCRIMINATOR.to_vec();
+    data.extend(base_amount_out.to_le_bytes());
+    data.extend(0u64.to_le_bytes());
+    let (tx, sig) = send(
+        &rpc,
+        &payer,
+        Instruction {
+            data,
+            accounts: common::sell_accounts(&payer.pubkey(), &mint.pubkey()),
+            program_id: PUMPFUN_AMM_PROGRAM,
+        },
+    )
+    .await?;
+
+    let balances = Balances::recorded(&rpc, &tx, &sig).await?;
+    let pool = pool_data(&rpc, &payer.pubkey(), &mint.pubkey(), &accounts, &balances).await?;
+    let quote_amount_out = pool.quote_out(base_amount_out)?;
+    let fees = pool.fees(pool.quote_out_before_fees(base_amount_out)?)?;
+    info!(
+        "Sell {}: reserves {}/{}, base in {}, quote out {}, {:?}",
+        sig, pool.reserve_base, pool.reserve_quote, base_amount_out, quote_amount_out, fees
+    );
+
+    assert_eq!(
+        balances.delta(&accounts.pool_base_ata),
+        base_amount_out as i128
+    );
+    assert_eq!(
+        balances.delta(&accounts.user_quote_ata),
+        quote_amount_out as i128
+    );
+    assert_eq!(
+        balances.delta(&accounts.pool_quote_ata),
+        -((quote_amount_out + fees.protocol_fee + fees.coin_creator_fee) as i128)
+    );
+    check_fees(&accounts, &balances, &fees);
+
+    Ok(())
+}
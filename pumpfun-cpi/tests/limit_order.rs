@@ -0,0 +1,67 @@
+mod common;
+
+use common::TestAccount;
+use pumpfun_amm::{
+    order::{derive_order, derive_order_authority, Order},
+    Direction, Error, PRICE_SCALE,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn order(direction: Direction, amount_in: u64, limit_price: u128) -> Order {
+    let owner = Pubkey::new_unique();
+    let (order, bump) = derive_order(&owner, 7);
+    Order {
+        owner,
+        pool: Pubkey::new_unique(),
+        direction,
+        amount_in,
+        limit_price,
+        expires_at_slot: 1_000,
+        keeper_tip: 5_000,
+        nonce: 7,
+        bump,
+        authority_bump: derive_order_authority(&order).1,
+    }
+}
+
+#[test]
+fn test_buy_order_min_output() {
+    // Buying with 1_000 quote at no more than 2 quote per base needs at least 500 base
+    let buy = order(Direction::Buy, 1_000, 2 * PRICE_SCALE);
+    assert_eq!(buy.min_output().unwrap(), 500);
+
+    // Rounded up so the fill never beats the limit price, 500.5 base needs 501
+    let buy = order(Direction::Buy, 1_001, 2 * PRICE_SCALE);
+    assert_eq!(buy.min_output().unwrap(), 501);
+
+    // A limit price below a quote atom per base atom asks for more base than quote in
+    let buy = order(Direction::Buy, 1_000, PRICE_SCALE / 4);
+    assert_eq!(buy.min_output().unwrap(), 4_000);
+}
+
+#[test]
+fn test_sell_order_min_output() {
+    // Selling 500 base at no less than 2 quote per base needs at least 1_000 quote
+    let sell = order(Direction::Sell, 500, 2 * PRICE_SCALE);
+    assert_eq!(sell.min_output().unwrap(), 1_000);
+
+    // 1.5 quote rounds up to 2, a fill of 1 quote would sell below the limit
+    let sell = order(Direction::Sell, 3, PRICE_SCALE / 2);
+    assert_eq!(sell.min_output().unwrap(), 2);
+}
+
+#[test]
+fn test_order_rejects_copy_at_other_address() {
+    let order = order(Direction::Sell, 500, PRICE_SCALE);
+    let (key, _) = derive_order(&order.owner, order.nonce);
+    let mut account = TestAccount::router(key, Order::LEN);
+    order.store(&account.info()).unwrap();
+    assert_eq!(Order::load(&account.info()).unwrap(), order);
+
+    // The same bytes in another router account must not pass for the order
+    account.key = Pubkey::new_unique();
+    assert!(matches!(
+        Order::load(&account.info()),
+        Err(Error::InvalidOrderAddress)
+    ));
+}
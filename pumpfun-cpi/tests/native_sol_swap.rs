@@ -0,0 +1,163 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+
+#[tokio::test]
+async fn test_native_sol_swap() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    // No `transfer_wsol`, the router wraps the lamports itself
+    let buy = pumpfun_amm::Instructions::ExecuteSwap(
+        pumpfun_amm::BuyInstruction::new(
+            (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+            100, // 1%
+            1,
+        )
+        .with_native_sol(),
+    );
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&buy, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, common::buy_accounts(&payer.pubkey(), &mint.pubkey()))
+            .await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Native SOL buy successful: {}", sig);
+
+    let mint_ata =
+        spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint.pubkey());
+    let wsol_ata = spl_associated_token_account::get_associated_token_address(
+        &payer.pubkey(),
+        &spl_token::native_mint::id(),
+    );
+
+    assert!(
+        rpc.get_account(&wsol_ata).await.is_err(),
+        "expected WSOL ATA to be closed after the buy"
+    );
+
+    let base_balance = rpc
+        .get_token_account_balance(&mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    let lamports = rpc.get_balance(&payer.pubkey()).await?;
+
+    let sell = pumpfun_amm::Instructions::ExecuteSell(
+        pumpfun_amm::SellInstruction::new(
+            base_balance,
+            100, // 1%
+            1,
+        )
+        .with_native_sol(),
+    );
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&sell, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, common::sell_accounts(&payer.pubkey(), &mint.pubkey()))
+            .await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Native SOL sell successful: {}", sig);
+
+    assert!(
+        rpc.get_account(&wsol_ata).await.is_err(),
+        "expected WSOL ATA to be closed after the sell"
+    );
+    assert!(
+        rpc.get_balance(&payer.pubkey()).await? > lamports,
+        "expected the sell to pay out lamports"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_native_sol_swap_with_wsol_balance() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    // WSOL held before the swap would be unwrapped along with the swap lamports
+    common::transfer_wsol(&rpc, &payer, (0.001 * LAMPORTS_PER_SOL as f64) as u64).await?;
+
+    let buy = pumpfun_amm::Instructions::ExecuteSwap(
+        pumpfun_amm::BuyInstruction::new(
+            (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+            100, // 1%
+            1,
+        )
+        .with_native_sol(),
+    );
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&buy, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, common::buy_accounts(&payer.pubkey(), &mint.pubkey()))
+            .await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    assert!(
+        rpc.send_and_confirm_transaction(&tx).await.is_err(),
+        "expected the native SOL buy to revert"
+    );
+
+    let wsol_ata = spl_associated_token_account::get_associated_token_address(
+        &payer.pubkey(),
+        &spl_token::native_mint::id(),
+    );
+    let wsol_balance = rpc
+        .get_token_account_balance(&wsol_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    assert_eq!(wsol_balance, (0.001 * LAMPORTS_PER_SOL as f64) as u64);
+
+    Ok(())
+}
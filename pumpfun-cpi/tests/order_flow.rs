@@ -0,0 +1,255 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use pumpfun_amm::{
+    order::{derive_order, derive_order_authority},
+    Direction, PRICE_SCALE,
+};
+use pumpfun_global::{derive_amm_user_volume_accumulator, derive_pool};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+
+fn transaction(
+    payer: &Keypair,
+    instruction: &pumpfun_amm::Instructions,
+    accounts: Vec<AccountMeta>,
+) -> Result<Transaction, Error> {
+    let mut data = vec![];
+    BorshSerialize::serialize(instruction, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    Ok(Transaction::new_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+    ))
+}
+
+async fn send(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    instruction: &pumpfun_amm::Instructions,
+    accounts: Vec<AccountMeta>,
+) -> Result<(), Error> {
+    let mut tx = transaction(payer, instruction, accounts)?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+    println!("{:?} successful: {}", instruction, sig);
+
+    Ok(())
+}
+
+async fn balance(rpc: &RpcClient, ata: &Pubkey) -> Result<u64, Error> {
+    Ok(rpc.get_token_account_balance(ata).await?.amount.parse()?)
+}
+
+fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(owner, mint)
+}
+
+/// Places a buy order of the pool created by `setup`, returning the order and its authority.
+async fn place_buy_order(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    mint: &Pubkey,
+    nonce: u64,
+    amount_in: u64,
+    limit_price: u128,
+) -> Result<(Pubkey, Pubkey), Error> {
+    let owner = payer.pubkey();
+    let wsol = spl_token::native_mint::id();
+    let (order, _) = derive_order(&owner, nonce);
+    let (authority, _) = derive_order_authority(&order);
+    let (pool, _) = derive_pool(0, &owner, mint, &wsol);
+
+    let instruction =
+        pumpfun_amm::Instructions::PlaceOrder(pumpfun_amm::PlaceOrderInstruction::new(
+            nonce,
+            Direction::Buy,
+            amount_in,
+            limit_price,
+            rpc.get_slot().await? + 1_000,
+            5_000,
+        ));
+    let mut accounts = vec![
+        AccountMeta::new(order, false),
+        AccountMeta::new_readonly(authority, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(pool, false),
+        AccountMeta::new_readonly(wsol, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(ata(&owner, &wsol), false),
+        AccountMeta::new(ata(&authority, &wsol), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(ata(&authority, mint), false),
+        AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+    accounts.extend(common::volume_accumulator_accounts(&authority));
+    send(rpc, payer, &instruction, accounts).await?;
+
+    Ok((order, authority))
+}
+
+async fn fill_accounts(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    order: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Vec<AccountMeta>, Error> {
+    let mut accounts = vec![
+        AccountMeta::new(*order, false),
+        AccountMeta::new(*owner, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(ata(owner, mint), false),
+        AccountMeta::new(ata(owner, &spl_token::native_mint::id()), false),
+    ];
+    accounts.extend(common::authority_buy_accounts(authority, owner, mint));
+
+    common::with_config(rpc, accounts).await
+}
+
+#[tokio::test]
+async fn test_limit_order_flow() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let owner = payer.pubkey();
+    let mint = mint.pubkey();
+    let wsol = spl_token::native_mint::id();
+    let amount_in = (0.001 * LAMPORTS_PER_SOL as f64) as u64;
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    // Paying up to one quote per base is far above the pool price, so the order is crossed
+    let (order, authority) =
+        place_buy_order(&rpc, &payer, &mint, nonce, amount_in, PRICE_SCALE).await?;
+    let quote_before = balance(&rpc, &ata(&owner, &wsol)).await?;
+    let base_before = balance(&rpc, &ata(&owner, &mint)).await?;
+
+    // The authority of a fresh order never traded and holds no lamports, PumpSwap `buy` only
+    // goes through because placing the order created its volume accumulator
+    assert_eq!(rpc.get_balance(&authority).await?, 0);
+    let (user_volume_accumulator, _) = derive_amm_user_volume_accumulator(&authority);
+    assert!(rpc.get_account(&user_volume_accumulator).await.is_ok());
+
+    send(
+        &rpc,
+        &payer,
+        &pumpfun_amm::Instructions::FillOrder,
+        fill_accounts(&rpc, &owner, &order, &authority, &mint).await?,
+    )
+    .await?;
+
+    assert!(
+        rpc.get_account(&order).await.is_err(),
+        "expected the filled order closed"
+    );
+    assert!(balance(&rpc, &ata(&owner, &mint)).await? > base_before);
+    assert!(balance(&rpc, &ata(&owner, &wsol)).await? >= quote_before);
+
+    // A trillionth of a quote per base is never crossed, the order can only be cancelled
+    let (order, authority) = place_buy_order(
+        &rpc,
+        &payer,
+        &mint,
+        nonce + 1,
+        amount_in,
+        PRICE_SCALE / 1_000_000_000_000,
+    )
+    .await?;
+    let quote_placed = balance(&rpc, &ata(&owner, &wsol)).await?;
+
+    let mut tx = transaction(
+        &payer,
+        &pumpfun_amm::Instructions::FillOrder,
+        fill_accounts(&rpc, &owner, &order, &authority, &mint).await?,
+    )?;
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let simulation = rpc.simulate_transaction(&tx).await?.value;
+    let expected = format!("Error: {}", pumpfun_amm::Error::OrderNotCrossed);
+    assert!(
+        simulation
+            .logs
+            .unwrap_or_default()
+            .iter()
+            .any(|log| log.ends_with(&expected)),
+        "expected the fill to revert as not crossed"
+    );
+
+    // Dust sent to the authority output account must not block the cancel
+    let mut tx = Transaction::new_with_payer(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            &ata(&owner, &mint),
+            &ata(&authority, &mint),
+            &owner,
+            &[],
+            1,
+        )?],
+        Some(&owner),
+    );
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    rpc.send_and_confirm_transaction(&tx).await?;
+    let base_dusted = balance(&rpc, &ata(&owner, &mint)).await?;
+
+    let accounts = vec![
+        AccountMeta::new(order, false),
+        AccountMeta::new_readonly(authority, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(wsol, false),
+        AccountMeta::new(ata(&owner, &wsol), false),
+        AccountMeta::new(ata(&authority, &wsol), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new(ata(&owner, &mint), false),
+        AccountMeta::new(ata(&authority, &mint), false),
+    ];
+    send(
+        &rpc,
+        &payer,
+        &pumpfun_amm::Instructions::CancelOrder,
+        accounts,
+    )
+    .await?;
+
+    assert!(
+        rpc.get_account(&order).await.is_err(),
+        "expected the cancelled order closed"
+    );
+    assert!(rpc.get_account(&ata(&authority, &mint)).await.is_err());
+    assert_eq!(
+        balance(&rpc, &ata(&owner, &wsol)).await?,
+        quote_placed + amount_in
+    );
+    assert_eq!(balance(&rpc, &ata(&owner, &mint)).await?, base_dusted + 1);
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::TestAccount;
+use pumpfun_amm::{
+    derive_global_config, Error, Pool, PoolData, PoolGlobalConfig, NATIVE_MINT, PUMPFUN_AMM_PROGRAM,
+};
+use pumpfun_global::derive_pool;
+use solana_sdk::pubkey::Pubkey;
+
+/// Pool, global config, pool base and pool quote token accounts of a PumpSwap pool.
+fn pool_accounts() -> [TestAccount; 4] {
+    let creator = Pubkey::new_unique();
+    let base_mint = Pubkey::new_unique();
+    let (pool_key, pool_bump) = derive_pool(0, &creator, &base_mint, &NATIVE_MINT);
+    let pool = Pool {
+        pool_bump,
+        index: 0,
+        creator,
+        base_mint,
+        quote_mint: NATIVE_MINT,
+        lp_mint: Pubkey::new_unique(),
+        pool_base_token_account: Pubkey::new_unique(),
+        pool_quote_token_account: Pubkey::new_unique(),
+        lp_supply: 0,
+        coin_creator: creator,
+    };
+    let global_config = PoolGlobalConfig {
+        admin: Pubkey::new_unique(),
+        lp_fee_basis_points: 20,
+        protocol_fee_basis_points: 5,
+        disable_flags: 0,
+        protocol_fee_recipients: [Pubkey::new_unique(); 8],
+        coin_creator_fee_basis_points: 5,
+        admin_set_coin_creator_authority: Pubkey::default(),
+    };
+
+    [
+        TestAccount::anchor(
+            pool_key,
+            PUMPFUN_AMM_PROGRAM,
+            PoolData::POOL_DISCRIMINATOR,
+            &pool,
+            243,
+        ),
+        TestAccount::anchor(
+            derive_global_config().0,
+            PUMPFUN_AMM_PROGRAM,
+            PoolData::GLOBAL_CONFIG_DISCRIMINATOR,
+            &global_config,
+            353,
+        ),
+        TestAccount::token(
+            pool.pool_base_token_account,
+            &base_mint,
+            &pool_key,
+            1_000_000_000,
+        ),
+        TestAccount::token(
+            pool.pool_quote_token_account,
+            &NATIVE_MINT,
+            &pool_key,
+            25_000_000,
+        ),
+    ]
+}
+
+fn load(accounts: &mut [TestAccount; 4]) -> Result<PoolData, Error> {
+    let [pool, global_config, pool_base_ata, pool_quote_ata] = accounts;
+    PoolData::new(
+        &pool.info(),
+        &global_config.info(),
+        &pool_base_ata.info(),
+        &pool_quote_ata.info(),
+    )
+}
+
+#[test]
+fn test_reserves_from_pool_token_accounts() {
+    let mut accounts = pool_accounts();
+    let pool = load(&mut accounts).unwrap();
+
+    assert_eq!(pool.reserve_base, 1_000_000_000);
+    assert_eq!(pool.reserve_quote, 25_000_000);
+}
+
+#[test]
+fn test_rejects_foreign_pool_token_account() {
+    let mut accounts = pool_accounts();
+    accounts[2].key = Pubkey::new_unique();
+
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::PoolTokenAccountMismatch)
+    ));
+}
+
+#[test]
+fn test_rejects_pool_token_account_of_other_mint() {
+    let mut accounts = pool_accounts();
+    accounts[3].data[..32].copy_from_slice(Pubkey::new_unique().as_ref());
+
+    assert!(matches!(load(&mut accounts), Err(Error::MintMismatch)));
+}
+
+#[test]
+fn test_rejects_pool_token_account_not_owned_by_token_program() {
+    let mut accounts = pool_accounts();
+    accounts[2].owner = Pubkey::new_unique();
+
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidTokenAccount)
+    ));
+}
+
+#[test]
+fn test_rejects_pool_not_owned_by_pumpswap() {
+    let mut accounts = pool_accounts();
+    accounts[0].owner = Pubkey::new_unique();
+
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidAccountOwner)
+    ));
+}
+
+#[test]
+fn test_rejects_pool_of_other_account_type() {
+    let mut accounts = pool_accounts();
+    accounts[0].data[..8].copy_from_slice(PoolData::GLOBAL_CONFIG_DISCRIMINATOR);
+
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidAccountDiscriminator)
+    ));
+}
+
+#[test]
+fn test_rejects_pool_at_other_address() {
+    // A copy of a genuine pool under another PumpSwap owned address
+    let mut accounts = pool_accounts();
+    accounts[0].key = Pubkey::new_unique();
+
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidPoolAddress)
+    ));
+}
+
+#[test]
+fn test_rejects_forged_global_config() {
+    let mut accounts = pool_accounts();
+    accounts[1].owner = Pubkey::new_unique();
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidAccountOwner)
+    ));
+
+    let mut accounts = pool_accounts();
+    accounts[1].data[..8].copy_from_slice(PoolData::POOL_DISCRIMINATOR);
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidAccountDiscriminator)
+    ));
+
+    let mut accounts = pool_accounts();
+    accounts[1].key = Pubkey::new_unique();
+    assert!(matches!(
+        load(&mut accounts),
+        Err(Error::InvalidGlobalConfigAddress)
+    ));
+}
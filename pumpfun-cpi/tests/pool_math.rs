@@ -0,0 +1,306 @@
+use pumpfun_amm::{
+    split_buy, BuyInstruction, Direction, DisableFlags, Error, ExpectedReserves, Pool, PoolData,
+    PoolGlobalConfig, PRICE_SCALE,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn pool_data(reserve_base: u64, reserve_quote: u64) -> PoolData {
+    pool_data_with_creator(reserve_base, reserve_quote, Pubkey::new_unique())
+}
+
+fn pool_data_with_creator(reserve_base: u64, reserve_quote: u64, coin_creator: Pubkey) -> PoolData {
+    PoolData {
+        reserve_base,
+        reserve_quote,
+        global_config: PoolGlobalConfig {
+            admin: Pubkey::default(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            disable_flags: 0,
+            protocol_fee_recipients: [Pubkey::default(); 8],
+            coin_creator_fee_basis_points: 5,
+            admin_set_coin_creator_authority: Pubkey::default(),
+        },
+        pool: Pool {
+            pool_bump: 255,
+            index: 0,
+            creator: Pubkey::default(),
+            base_mint: Pubkey::default(),
+            quote_mint: spl_token::native_mint::id(),
+            lp_mint: Pubkey::default(),
+            pool_base_token_account: Pubkey::default(),
+            pool_quote_token_account: Pubkey::default(),
+            lp_supply: 0,
+            coin_creator,
+        },
+        platform_fee_bps: 0,
+    }
+}
+
+#[test]
+fn test_spot_price() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    assert_eq!(pool.spot_price().unwrap(), PRICE_SCALE / 40);
+}
+
+#[test]
+fn test_quote_buy() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    let quote = pool.quote(Direction::Buy, 250_000).unwrap();
+
+    assert_eq!(quote.output_amount, pool.base_out(250_000).unwrap());
+    assert!(quote.output_amount < 250_000 * 40);
+    // 1% of the reserve moves the price by roughly 1% plus 0.3% of fees
+    assert!((120..=140).contains(&quote.price_impact_bps), "{:?}", quote);
+}
+
+#[test]
+fn test_quote_sell() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    let quote = pool.quote(Direction::Sell, 10_000_000).unwrap();
+
+    assert_eq!(quote.output_amount, pool.quote_out(10_000_000).unwrap());
+    assert!(quote.output_amount < 250_000);
+    assert!((120..=140).contains(&quote.price_impact_bps), "{:?}", quote);
+}
+
+#[test]
+fn test_price_impact_grows_with_size() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    let small = pool.quote(Direction::Buy, 25_000).unwrap();
+    let large = pool.quote(Direction::Buy, 10_000_000).unwrap();
+
+    assert!(small.price_impact_bps < large.price_impact_bps);
+    assert!(large.price_impact_bps > 4_000, "{:?}", large);
+}
+
+#[test]
+fn test_price_impact_cap() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    let quote = pool.quote(Direction::Buy, 250_000).unwrap();
+    let check = |max_price_impact_bps| {
+        pool.check_price_impact(
+            Direction::Buy,
+            250_000,
+            quote.output_amount,
+            max_price_impact_bps,
+        )
+    };
+
+    assert!(check(None).is_ok());
+    assert!(check(Some(quote.price_impact_bps)).is_ok());
+    assert!(matches!(
+        check(Some(quote.price_impact_bps - 1)),
+        Err(Error::PriceImpactTooHigh)
+    ));
+}
+
+#[test]
+fn test_quote_in_covers_base_out() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    let (quote_in, fees) = pool.quote_in(10_000_000).unwrap();
+
+    // Raw curve cost is ceil(25_000_000 * 10_000_000 / 990_000_000) = 252_526
+    assert_eq!(fees.lp_fee, 506);
+    assert_eq!(fees.protocol_fee, 127);
+    assert_eq!(fees.coin_creator_fee, 127);
+    assert_eq!(quote_in, 252_526 + 506 + 127 + 127);
+    assert!(pool.base_out(quote_in).unwrap() >= 10_000_000 - 1);
+}
+
+#[test]
+fn test_quote_in_exceeding_reserve() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+
+    assert!(pool.quote_in(1_000_000_000).is_err());
+}
+
+// Reserves of a freshly migrated pool. The fee arithmetic is checked against PumpSwap itself
+// by the devnet test in `fee_vectors.rs`.
+const RESERVE_BASE: u64 = 206_900_000_000_000;
+const RESERVE_QUOTE: u64 = 84_990_359_400;
+
+#[test]
+fn test_buy_quote_input_round_trip() {
+    let with_creator = pool_data(RESERVE_BASE, RESERVE_QUOTE);
+    let without_creator = pool_data_with_creator(RESERVE_BASE, RESERVE_QUOTE, Pubkey::default());
+
+    for pool in [&with_creator, &without_creator] {
+        for quote_in in [1_000_000_000, 12_345] {
+            // The AMM charges exactly the quote the router offered
+            let base_out = pool.base_out(quote_in).unwrap();
+            assert_eq!(pool.quote_in(base_out).unwrap().0, quote_in);
+        }
+    }
+}
+
+#[test]
+fn test_no_coin_creator_fee_without_coin_creator() {
+    let with_creator = pool_data(RESERVE_BASE, RESERVE_QUOTE);
+    let without_creator = pool_data_with_creator(RESERVE_BASE, RESERVE_QUOTE, Pubkey::default());
+
+    let (quote_in, fees) = with_creator.quote_in(1_000_000_000_000).unwrap();
+    let (quote_in_without, fees_without) = without_creator.quote_in(1_000_000_000_000).unwrap();
+    assert!(fees.coin_creator_fee > 0);
+    assert_eq!(fees_without.coin_creator_fee, 0);
+    assert_eq!(quote_in - quote_in_without, fees.coin_creator_fee);
+
+    let quote_out_before_fees = without_creator
+        .quote_out_before_fees(1_000_000_000_000)
+        .unwrap();
+    assert_eq!(
+        without_creator
+            .fees(quote_out_before_fees)
+            .unwrap()
+            .coin_creator_fee,
+        0
+    );
+}
+
+#[test]
+fn test_disable_flags() {
+    let mut pool = pool_data(1_000_000_000, 25_000_000);
+    assert!(pool.global_config.check_enabled(DisableFlags::BUY).is_ok());
+
+    pool.global_config.disable_flags = (DisableFlags::DEPOSIT | DisableFlags::SELL).bits();
+    assert_eq!(
+        pool.global_config.disabled_operations(),
+        DisableFlags::DEPOSIT | DisableFlags::SELL
+    );
+    assert!(pool.global_config.check_enabled(DisableFlags::BUY).is_ok());
+    assert!(matches!(
+        pool.global_config.check_enabled(DisableFlags::SELL),
+        Err(Error::OperationDisabled(DisableFlags::SELL))
+    ));
+}
+
+#[test]
+fn test_platform_fee_in_quotes() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    let with_fee = pool_data(1_000_000_000, 25_000_000).with_platform_fee_bps(100);
+
+    // Buys skim the fee from the quote in before it reaches the pool
+    assert_eq!(with_fee.platform_fee(1_000_001).unwrap(), 10_001);
+    assert_eq!(
+        with_fee.base_out(1_000_001).unwrap(),
+        pool.base_out(990_000).unwrap()
+    );
+
+    // Sells skim it from the quote the pool pays out
+    let gross = pool.quote_out(10_000_000).unwrap();
+    assert_eq!(
+        with_fee.quote_out_before_platform_fee(10_000_000).unwrap(),
+        gross
+    );
+    assert_eq!(
+        with_fee.quote_out(10_000_000).unwrap(),
+        gross - with_fee.platform_fee(gross).unwrap()
+    );
+}
+
+#[test]
+fn test_max_quote_before_platform_fee() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    assert_eq!(
+        pool.max_quote_before_platform_fee(1_010_000).unwrap(),
+        1_010_000
+    );
+
+    let with_fee = pool.with_platform_fee_bps(100);
+    assert_eq!(
+        with_fee.max_quote_before_platform_fee(1_010_000).unwrap(),
+        1_000_000
+    );
+
+    // The most PumpSwap may charge without the rounded up fee taking the total past the max
+    let total = |quote: u64| quote + with_fee.platform_fee(quote).unwrap();
+    for max_quote_in in (0..5_000).chain(1_009_990..1_010_010) {
+        let quote = with_fee
+            .max_quote_before_platform_fee(max_quote_in)
+            .unwrap();
+        assert!(total(quote) <= max_quote_in, "{}", max_quote_in);
+        assert!(total(quote + 1) > max_quote_in, "{}", max_quote_in);
+    }
+}
+
+#[test]
+fn test_valid_until_slot() {
+    let buy = BuyInstruction::new(1_000, 100, 1);
+    assert!(buy.check_valid_until(u64::MAX).is_ok());
+
+    let buy = buy.with_valid_until_slot(1_000);
+    assert!(buy.check_valid_until(1_000).is_ok());
+    assert!(matches!(
+        buy.check_valid_until(1_001),
+        Err(Error::SwapExpired)
+    ));
+}
+
+#[test]
+fn test_check_reserves() {
+    let pool = pool_data(1_000_000_000, 25_000_000);
+    assert!(pool
+        .check_reserves(&ExpectedReserves::new(1_000_000_000, 25_000_000, 0))
+        .is_ok());
+
+    // 0.8% off on the quote side, within a 1% tolerance but not within 0.5%
+    assert!(pool
+        .check_reserves(&ExpectedReserves::new(1_000_000_000, 24_800_000, 100))
+        .is_ok());
+    assert!(matches!(
+        pool.check_reserves(&ExpectedReserves::new(1_000_000_000, 24_800_000, 50)),
+        Err(Error::ReservesDrifted)
+    ));
+    assert!(matches!(
+        pool.check_reserves(&ExpectedReserves::new(1_100_000_000, 25_000_000, 500)),
+        Err(Error::ReservesDrifted)
+    ));
+}
+
+#[test]
+fn test_split_buy_follows_liquidity() {
+    let pools = [
+        pool_data(1_000_000_000, 25_000_000),
+        pool_data(4_000_000_000, 100_000_000),
+    ];
+
+    // Same price, so the input is split by the depth of the pools
+    let allocations = split_buy(&pools, 10_000_000).unwrap();
+    assert_eq!(allocations.iter().sum::<u64>(), 10_000_000);
+    assert!(allocations[0].abs_diff(2_000_000) <= 1, "{:?}", allocations);
+
+    let split =
+        pools[0].base_out(allocations[0]).unwrap() + pools[1].base_out(allocations[1]).unwrap();
+    assert!(split > pools[1].base_out(10_000_000).unwrap());
+}
+
+#[test]
+fn test_split_buy_skips_expensive_pool() {
+    let pools = [
+        pool_data(1_000_000_000, 25_000_000),
+        // Twice the price, not worth buying from for a small input
+        pool_data(1_000_000_000, 50_000_000),
+    ];
+
+    assert_eq!(split_buy(&pools, 1_000_000).unwrap(), vec![1_000_000, 0]);
+}
+
+#[test]
+fn test_split_buy_weighs_pool_fees() {
+    let mut expensive = pool_data(1_000_000_000, 25_000_000);
+    expensive.global_config.lp_fee_basis_points = 500;
+    let pools = [pool_data(1_000_000_000, 25_000_000), expensive];
+
+    // Same reserves, the pool charging 5% of fees gets less of the input
+    let allocations = split_buy(&pools, 10_000_000).unwrap();
+    assert_eq!(allocations.iter().sum::<u64>(), 10_000_000);
+    assert!(allocations[0] > allocations[1], "{:?}", allocations);
+
+    // Moving 1% of the input between the pools either way gets less base out
+    let base_out = |first: u64| {
+        pools[0].base_out(first).unwrap() + pools[1].base_out(10_000_000 - first).unwrap()
+    };
+    let split = base_out(allocations[0]);
+    assert!(split >= base_out(allocations[0] - 100_000));
+    assert!(split >= base_out(allocations[0] + 100_000));
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+
+#[tokio::test]
+async fn test_price_impact_swap() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let accounts = common::buy_accounts(&payer.pubkey(), &mint.pubkey());
+
+    assert!(accounts.len() == 21, "{}", accounts.len());
+    // Spending as much as the pool holds roughly doubles the price
+    let instruction = pumpfun_amm::Instructions::ExecuteSwap(
+        pumpfun_amm::BuyInstruction::new(
+            (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+            100, // 1%
+            1,
+        )
+        .with_max_price_impact_bps(1_000), // 10%
+    );
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&instruction, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[payer], recent_blockhash);
+    assert!(
+        rpc.send_and_confirm_transaction(&tx).await.is_err(),
+        "expected the price impact check to fail"
+    );
+
+    Ok(())
+}
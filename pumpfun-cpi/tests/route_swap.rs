@@ -0,0 +1,113 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use pumpfun_amm::Direction;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+
+#[tokio::test]
+async fn test_route_swap() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    let TestData {
+        mint: other_mint, ..
+    } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let buy = pumpfun_amm::Instructions::ExecuteSwap(pumpfun_amm::BuyInstruction::new(
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        100, // 1%
+        1,
+    ));
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&buy, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, common::buy_accounts(&payer.pubkey(), &mint.pubkey()))
+            .await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Buy execution successful: {}", sig);
+
+    let mint_ata =
+        spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint.pubkey());
+    let other_mint_ata = spl_associated_token_account::get_associated_token_address(
+        &payer.pubkey(),
+        &other_mint.pubkey(),
+    );
+
+    let base_balance = rpc
+        .get_token_account_balance(&mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    let other_balance = rpc
+        .get_token_account_balance(&other_mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+
+    // Token A -> SOL -> token B
+    let mut accounts = common::sell_accounts(&payer.pubkey(), &mint.pubkey());
+    accounts.extend(common::buy_accounts(&payer.pubkey(), &other_mint.pubkey()));
+
+    let route = pumpfun_amm::Instructions::RouteSwap(pumpfun_amm::RouteSwapInstruction::new(
+        base_balance,
+        1,
+        vec![Direction::Sell, Direction::Buy],
+    ));
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&route, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Route swap successful: {}", sig);
+
+    let balance = rpc
+        .get_token_account_balance(&other_mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    assert!(
+        balance > other_balance,
+        "expected the second token balance to increase"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use common::TestAccount;
+use pumpfun_amm::{
+    config::{check_fees, derive_config, Referral, RouterConfig},
+    Error, TokenAccount, NATIVE_MINT, SYSTEM_PROGRAM,
+};
+use solana_sdk::{account_info::AccountInfo, pubkey::Pubkey};
+
+#[test]
+fn test_router_config_round_trip() {
+    let (key, bump) = derive_config();
+    let owner = pumpfun_amm::id();
+    let admin = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![0; RouterConfig::LEN];
+    let account = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    let mut config = RouterConfig::new(admin, 25, Pubkey::new_unique(), 2_000, bump);
+    config.store(&account).unwrap();
+    assert_eq!(RouterConfig::load(&account).unwrap(), config);
+    assert!(config.check_active().is_ok());
+
+    config.paused = true;
+    config.store(&account).unwrap();
+    assert!(matches!(
+        RouterConfig::load(&account).unwrap().check_active(),
+        Err(Error::RouterPaused)
+    ));
+}
+
+#[test]
+fn test_router_config_rejects_foreign_accounts() {
+    let (_, bump) = derive_config();
+    let key = Pubkey::new_unique();
+    let owner = pumpfun_amm::id();
+    let mut lamports = 0;
+    let mut data = vec![0; RouterConfig::LEN];
+    let account = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    RouterConfig::new(Pubkey::new_unique(), 0, Pubkey::new_unique(), 0, bump)
+        .store(&account)
+        .unwrap();
+    assert!(matches!(
+        RouterConfig::load(&account),
+        Err(Error::InvalidRouterConfigAddress)
+    ));
+}
+
+#[test]
+fn test_router_config_admin() {
+    let admin = Pubkey::new_unique();
+    let config = RouterConfig::new(admin, 0, Pubkey::new_unique(), 0, 255);
+
+    let owner = Pubkey::default();
+    let (mut lamports, mut data) = (0, vec![]);
+    let signer = AccountInfo::new(
+        &admin,
+        true,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert!(config.check_admin(&signer).is_ok());
+
+    let other = Pubkey::new_unique();
+    let (mut lamports, mut data) = (0, vec![]);
+    let intruder = AccountInfo::new(
+        &other,
+        true,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert!(matches!(
+        config.check_admin(&intruder),
+        Err(Error::InvalidRouterAdmin)
+    ));
+}
+
+fn wallet(key: Pubkey) -> TestAccount {
+    TestAccount::new(key, SYSTEM_PROGRAM, vec![])
+}
+
+#[test]
+fn test_router_config_rejects_non_admin() {
+    let admin = Pubkey::new_unique();
+    let mut config = RouterConfig::new(admin, 25, Pubkey::new_unique(), 2_000, 255);
+    let before = config.clone();
+    let mut intruder = wallet(Pubkey::new_unique());
+
+    assert!(matches!(
+        config.update(&intruder.signer(), 100, Pubkey::new_unique(), 0),
+        Err(Error::InvalidRouterAdmin)
+    ));
+    assert!(matches!(
+        config.set_paused(&intruder.signer(), true),
+        Err(Error::InvalidRouterAdmin)
+    ));
+    assert!(matches!(
+        config.transfer_admin(&intruder.signer(), Pubkey::new_unique()),
+        Err(Error::InvalidRouterAdmin)
+    ));
+
+    // The admin key without its signature
+    let mut admin = wallet(admin);
+    assert!(matches!(
+        config.set_paused(&admin.info(), true),
+        Err(Error::ProgramError(_))
+    ));
+    assert_eq!(config, before);
+
+    config.set_paused(&admin.signer(), true).unwrap();
+    assert!(matches!(config.check_active(), Err(Error::RouterPaused)));
+}
+
+#[test]
+fn test_router_config_two_step_admin_transfer() {
+    let mut admin = wallet(Pubkey::new_unique());
+    let mut new_admin = wallet(Pubkey::new_unique());
+    let mut config = RouterConfig::new(admin.key, 0, Pubkey::new_unique(), 0, 255);
+
+    // Nothing to accept before a transfer
+    assert!(matches!(
+        config.accept_admin(&new_admin.signer()),
+        Err(Error::InvalidPendingAdmin)
+    ));
+
+    config
+        .transfer_admin(&admin.signer(), new_admin.key)
+        .unwrap();
+    assert_eq!(config.pending_admin, new_admin.key);
+    // The current admin stays in charge until the new one accepts
+    assert!(config.set_paused(&admin.signer(), false).is_ok());
+
+    assert!(matches!(
+        config.accept_admin(&wallet(Pubkey::new_unique()).signer()),
+        Err(Error::InvalidPendingAdmin)
+    ));
+    assert!(matches!(
+        config.accept_admin(&new_admin.info()),
+        Err(Error::ProgramError(_))
+    ));
+
+    config.accept_admin(&new_admin.signer()).unwrap();
+    assert_eq!(config.admin, new_admin.key);
+    assert_eq!(config.pending_admin, Pubkey::default());
+    assert!(matches!(
+        config.set_paused(&admin.signer(), true),
+        Err(Error::InvalidRouterAdmin)
+    ));
+}
+
+#[test]
+fn test_fee_bounds() {
+    assert!(check_fees(0, 0).is_ok());
+    assert!(check_fees(9_999, 10_000).is_ok());
+    assert!(matches!(
+        check_fees(10_000, 0),
+        Err(Error::InvalidPlatformFee)
+    ));
+    assert!(matches!(
+        check_fees(100, 10_001),
+        Err(Error::InvalidReferralShare)
+    ));
+}
+
+#[test]
+fn test_referral_share() {
+    let config = RouterConfig::new(Pubkey::new_unique(), 100, Pubkey::new_unique(), 2_500, 255);
+    assert_eq!(config.referral_share(10_001).unwrap(), 2_500);
+    assert_eq!(config.referral_share(0).unwrap(), 0);
+}
+
+#[test]
+fn test_referrer_checks() {
+    let referrer = Pubkey::new_unique();
+    let referral = Referral::new(referrer, NATIVE_MINT, 255);
+    let ata = |mint, owner| TokenAccount {
+        mint,
+        owner,
+        amount: 0,
+    };
+    let trader = Pubkey::new_unique();
+
+    assert!(referral
+        .check_referrer(&NATIVE_MINT, &ata(NATIVE_MINT, referrer), &trader)
+        .is_ok());
+    assert!(matches!(
+        referral.check_referrer(&Pubkey::new_unique(), &ata(NATIVE_MINT, referrer), &trader),
+        Err(Error::InvalidReferral)
+    ));
+    assert!(matches!(
+        referral.check_referrer(&NATIVE_MINT, &ata(NATIVE_MINT, trader), &trader),
+        Err(Error::InvalidReferral)
+    ));
+
+    // A trader referring its own swaps would pay itself the referral share
+    assert!(matches!(
+        referral.check_referrer(&NATIVE_MINT, &ata(NATIVE_MINT, referrer), &referrer),
+        Err(Error::SelfReferral)
+    ));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use anyhow::Error;
+use borsh::BorshSerialize;
+use common::TestData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+
+#[tokio::test]
+async fn test_successful_sell() -> Result<(), Error> {
+    common::setup_logger();
+    let rpc =
+        RpcClient::new_with_commitment(common::DEV_NET.to_owned(), CommitmentConfig::finalized());
+
+    let TestData { payer, mint } = common::setup(
+        &rpc,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+    )
+    .await?;
+
+    common::transfer_wsol(&rpc, &payer, (0.005 * LAMPORTS_PER_SOL as f64) as u64).await?;
+    let buy = pumpfun_amm::Instructions::ExecuteSwap(pumpfun_amm::BuyInstruction::new(
+        (0.005 * LAMPORTS_PER_SOL as f64) as u64,
+        100, // 1%
+        1,
+    ));
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&buy, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, common::buy_accounts(&payer.pubkey(), &mint.pubkey()))
+            .await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Buy execution successful: {}", sig);
+
+    let mint_ata =
+        spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint.pubkey());
+    let wsol_ata = spl_associated_token_account::get_associated_token_address(
+        &payer.pubkey(),
+        &spl_token::native_mint::id(),
+    );
+
+    let base_balance = rpc
+        .get_token_account_balance(&mint_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+    let quote_balance = rpc
+        .get_token_account_balance(&wsol_ata)
+        .await?
+        .amount
+        .parse::<u64>()?;
+
+    let accounts = common::sell_accounts(&payer.pubkey(), &mint.pubkey());
+    assert!(accounts.len() == 19, "{}", accounts.len());
+    let sell = pumpfun_amm::Instructions::ExecuteSell(pumpfun_amm::SellInstruction::new(
+        base_balance,
+        100, // 1%
+        1,
+    ));
+
+    let mut data = vec![];
+    BorshSerialize::serialize(&sell, &mut data)?;
+
+    let instructions = &[Instruction {
+        data,
+        accounts: common::with_config(&rpc, accounts).await?,
+        program_id: pumpfun_amm::id(),
+    }];
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    tx.sign(&[&payer], recent_blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+
+    println!("Sell execution successful: {}", sig);
+
+    let balance = rpc.get_token_account_balance(&mint_ata).await?;
+    assert_eq!(
+        balance.amount.parse::<u64>()?,
+        0,
+        "expected all mint tokens to be sold"
+    );
+
+    let balance = rpc.get_token_account_balance(&wsol_ata).await?;
+    assert!(
+        balance.amount.parse::<u64>()? > quote_balance,
+        "expected WSOL ATA balance to increase"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use common::TestAccount;
+use pumpfun_amm::{
+    derive_associated_token_account, derive_coin_creator_vault_authority, derive_global_config,
+    Error, Pool, PoolData, PoolGlobalConfig, SwapAccounts, ASSOCIATED_TOKEN_PROGRAM, NATIVE_MINT,
+    PUMPFUN_AMM_PROGRAM, SPL_TOKEN_2022_PROGRAM, SPL_TOKEN_PROGRAM, SYSTEM_PROGRAM,
+};
+use pumpfun_global::{derive_pool, PUMP_FUN_AMM_EVENT_AUTHORITY};
+use solana_sdk::{account_info::AccountInfo, pubkey::Pubkey};
+
+fn pool_data() -> PoolData {
+    let creator = Pubkey::new_unique();
+    let base_mint = Pubkey::new_unique();
+    let (_, pool_bump) = derive_pool(0, &creator, &base_mint, &NATIVE_MINT);
+
+    PoolData {
+        reserve_base: 1_000_000_000,
+        reserve_quote: 25_000_000,
+        global_config: PoolGlobalConfig {
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            disable_flags: 0,
+            protocol_fee_recipients: [Pubkey::new_unique(); 8],
+            coin_creator_fee_basis_points: 5,
+            admin_set_coin_creator_authority: Pubkey::default(),
+        },
+        pool: Pool {
+            pool_bump,
+            index: 0,
+            creator,
+            base_mint,
+            quote_mint: NATIVE_MINT,
+            lp_mint: Pubkey::new_unique(),
+            pool_base_token_account: Pubkey::new_unique(),
+            pool_quote_token_account: Pubkey::new_unique(),
+            lp_supply: 0,
+            coin_creator: creator,
+        },
+        platform_fee_bps: 0,
+    }
+}
+
+fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    derive_associated_token_account(owner, &SPL_TOKEN_PROGRAM, mint).0
+}
+
+/// Router `ExecuteSell` accounts of `pool_data`, as a client builds them.
+fn swap_accounts(pool_data: &PoolData) -> Vec<TestAccount> {
+    let pool = &pool_data.pool;
+    let user = Pubkey::new_unique();
+    let protocol_fee_recipient = pool_data.global_config.protocol_fee_recipients[0];
+    let (coin_creator_vault_authority, _) = derive_coin_creator_vault_authority(&pool.coin_creator);
+    let (pool_key, _) = derive_pool(0, &pool.creator, &pool.base_mint, &pool.quote_mint);
+    let account = |key| TestAccount::new(key, SYSTEM_PROGRAM, vec![]);
+    let mint = |key| TestAccount::new(key, SPL_TOKEN_PROGRAM, vec![0; 82]);
+
+    vec![
+        TestAccount::new(pool_key, PUMPFUN_AMM_PROGRAM, vec![]),
+        account(user),
+        TestAccount::new(derive_global_config().0, PUMPFUN_AMM_PROGRAM, vec![]),
+        mint(pool.base_mint),
+        mint(pool.quote_mint),
+        account(ata(&user, &pool.base_mint)),
+        account(ata(&user, &pool.quote_mint)),
+        account(pool.pool_base_token_account),
+        account(pool.pool_quote_token_account),
+        account(protocol_fee_recipient),
+        account(ata(&protocol_fee_recipient, &pool.quote_mint)),
+        account(SPL_TOKEN_PROGRAM),
+        account(SPL_TOKEN_PROGRAM),
+        account(SYSTEM_PROGRAM),
+        account(ASSOCIATED_TOKEN_PROGRAM),
+        account(PUMP_FUN_AMM_EVENT_AUTHORITY),
+        account(PUMPFUN_AMM_PROGRAM),
+        account(ata(&coin_creator_vault_authority, &pool.quote_mint)),
+        account(coin_creator_vault_authority),
+    ]
+}
+
+fn with_swap<R>(accounts: &mut [TestAccount], f: impl FnOnce(&SwapAccounts) -> R) -> R {
+    let infos = accounts
+        .iter_mut()
+        .map(TestAccount::info)
+        .collect::<Vec<AccountInfo>>();
+    f(&SwapAccounts::next(&mut infos.iter()).unwrap())
+}
+
+fn check_programs(accounts: &mut [TestAccount]) -> Result<(), Error> {
+    with_swap(accounts, |swap| swap.check_programs())
+}
+
+#[test]
+fn test_accepts_pinned_programs() {
+    let mut accounts = swap_accounts(&pool_data());
+    assert!(check_programs(&mut accounts).is_ok());
+}
+
+#[test]
+fn test_rejects_foreign_pumpswap_program() {
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[16].key = Pubkey::new_unique();
+
+    assert!(matches!(
+        check_programs(&mut accounts),
+        Err(Error::InvalidPumpfunProgram)
+    ));
+}
+
+#[test]
+fn test_rejects_foreign_token_program() {
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[12].key = Pubkey::new_unique();
+    assert!(matches!(
+        check_programs(&mut accounts),
+        Err(Error::InvalidTokenProgram)
+    ));
+
+    // A genuine token program that does not own the mint
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[11].key = SPL_TOKEN_2022_PROGRAM;
+    assert!(matches!(
+        check_programs(&mut accounts),
+        Err(Error::InvalidTokenProgram)
+    ));
+}
+
+#[test]
+fn test_rejects_foreign_system_and_associated_token_programs() {
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[13].key = Pubkey::new_unique();
+    assert!(matches!(
+        check_programs(&mut accounts),
+        Err(Error::InvalidSystemProgram)
+    ));
+
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[14].key = Pubkey::new_unique();
+    assert!(matches!(
+        check_programs(&mut accounts),
+        Err(Error::InvalidAssociatedTokenProgram)
+    ));
+}
+
+fn check_pool_accounts(accounts: &mut [TestAccount], pool_data: &PoolData) -> Result<(), Error> {
+    with_swap(accounts, |swap| swap.check_pool_accounts(pool_data))
+}
+
+#[test]
+fn test_accepts_pool_accounts() {
+    let pool_data = pool_data();
+    let mut accounts = swap_accounts(&pool_data);
+    assert!(check_pool_accounts(&mut accounts, &pool_data).is_ok());
+}
+
+#[test]
+fn test_rejects_mints_of_other_pool() {
+    let pool_data = pool_data();
+    let mut accounts = swap_accounts(&pool_data);
+    accounts[3].key = Pubkey::new_unique();
+
+    assert!(matches!(
+        check_pool_accounts(&mut accounts, &pool_data),
+        Err(Error::MintMismatch)
+    ));
+}
+
+#[test]
+fn test_rejects_unknown_protocol_fee_recipient() {
+    let pool_data = pool_data();
+    let mut accounts = swap_accounts(&pool_data);
+    accounts[9].key = Pubkey::new_unique();
+    assert!(matches!(
+        check_pool_accounts(&mut accounts, &pool_data),
+        Err(Error::InvalidProtocolFeeRecipient)
+    ));
+
+    // Unused recipient slots are zeroed, the default key is never a recipient
+    let mut pool_data = pool_data;
+    pool_data.global_config.protocol_fee_recipients[7] = Pubkey::default();
+    let mut accounts = swap_accounts(&pool_data);
+    accounts[9].key = Pubkey::default();
+    assert!(matches!(
+        check_pool_accounts(&mut accounts, &pool_data),
+        Err(Error::InvalidProtocolFeeRecipient)
+    ));
+}
+
+#[test]
+fn test_rejects_foreign_protocol_fee_recipient_ata() {
+    let pool_data = pool_data();
+    let mut accounts = swap_accounts(&pool_data);
+    accounts[10].key = ata(&Pubkey::new_unique(), &NATIVE_MINT);
+
+    assert!(matches!(
+        check_pool_accounts(&mut accounts, &pool_data),
+        Err(Error::InvalidProtocolFeeRecipientAta)
+    ));
+}
+
+#[test]
+fn test_rejects_vault_of_other_coin_creator() {
+    let pool_data = pool_data();
+    let (other_authority, _) = derive_coin_creator_vault_authority(&Pubkey::new_unique());
+
+    let mut accounts = swap_accounts(&pool_data);
+    accounts[18].key = other_authority;
+    assert!(matches!(
+        check_pool_accounts(&mut accounts, &pool_data),
+        Err(Error::InvalidCoinCreatorVaultAuthority)
+    ));
+
+    let mut accounts = swap_accounts(&pool_data);
+    accounts[17].key = ata(&other_authority, &NATIVE_MINT);
+    assert!(matches!(
+        check_pool_accounts(&mut accounts, &pool_data),
+        Err(Error::InvalidCoinCreatorVaultAta)
+    ));
+}
+
+#[test]
+fn test_user_base_ata() {
+    let mut accounts = swap_accounts(&pool_data());
+    assert!(with_swap(&mut accounts, |swap| swap.check_user_base_ata()).is_ok());
+
+    // The account of another owner
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[5].key = ata(&Pubkey::new_unique(), &accounts[3].key);
+    assert!(matches!(
+        with_swap(&mut accounts, |swap| swap.check_user_base_ata()),
+        Err(Error::InvalidUserAta)
+    ));
+
+    // Derived with SPL Token for a Token-2022 mint
+    let mut accounts = swap_accounts(&pool_data());
+    accounts[3].owner = SPL_TOKEN_2022_PROGRAM;
+    assert!(matches!(
+        with_swap(&mut accounts, |swap| swap.check_user_base_ata()),
+        Err(Error::InvalidUserAta)
+    ));
+}
@@ -0,0 +1,64 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshSerialize;
+use pumpfun_amm::{Direction, SwapPerformed};
+use pumpfun_instructions::events::decode_swap_events;
+use solana_sdk::pubkey::Pubkey;
+
+fn event() -> SwapPerformed {
+    SwapPerformed {
+        pool: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        base_mint: Pubkey::new_unique(),
+        quote_mint: spl_token::native_mint::id(),
+        direction: Direction::Buy,
+        input_amount: 5_000_000,
+        output_amount: 1_234_567,
+        lp_fee: 10_000,
+        protocol_fee: 2_500,
+        coin_creator_fee: 2_500,
+        reserve_base: 900_000_000,
+        reserve_quote: 15_000_000,
+    }
+}
+
+fn program_data(event: &SwapPerformed) -> String {
+    let mut data = Vec::from(SwapPerformed::DISCRIMINATOR);
+    BorshSerialize::serialize(event, &mut data).unwrap();
+    format!("Program data: {}", STANDARD.encode(data))
+}
+
+#[test]
+fn test_decode_swap_events() {
+    let event = event();
+    let router = pumpfun_amm::id();
+    let other = Pubkey::new_unique();
+    let logs = vec![
+        format!("Program {} invoke [1]", router),
+        "Program log: Instruction: ExecuteSwap".to_owned(),
+        format!("Program {} invoke [2]", pumpfun_global::PUMPFUN_AMM_PROGRAM),
+        // Inner program data must be ignored even if it decodes
+        program_data(&event),
+        format!("Program {} success", pumpfun_global::PUMPFUN_AMM_PROGRAM),
+        program_data(&event),
+        format!("Program {} consumed 80000 of 200000 compute units", router),
+        format!("Program {} success", router),
+        format!("Program {} invoke [1]", other),
+        program_data(&event),
+        format!("Program {} success", other),
+    ];
+
+    assert_eq!(decode_swap_events(&logs), vec![event]);
+}
+
+#[test]
+fn test_decode_swap_events_skips_unrelated_data() {
+    let router = pumpfun_amm::id();
+    let logs = vec![
+        format!("Program {} invoke [1]", router),
+        format!("Program data: {}", STANDARD.encode([1u8, 2, 3])),
+        "Program data: not base64".to_owned(),
+        format!("Program {} success", router),
+    ];
+
+    assert!(decode_swap_events(&logs).is_empty());
+}
@@ -0,0 +1,42 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use pumpfun_amm::SwapResult;
+use pumpfun_instructions::return_data::swap_result;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::pubkey::Pubkey;
+
+fn simulation(program_id: &Pubkey, data: &[u8]) -> RpcSimulateTransactionResult {
+    serde_json::from_value(serde_json::json!({
+        "err": null,
+        "logs": [],
+        "returnData": {
+            "programId": program_id.to_string(),
+            "data": [STANDARD.encode(data), "base64"],
+        },
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_swap_result_from_simulation() {
+    let result = SwapResult {
+        input_amount: 5_000_000,
+        output_amount: 1_234_567,
+        reserve_base: 900_000_000,
+        reserve_quote: 15_000_000,
+    };
+
+    let simulation = simulation(&pumpfun_amm::id(), &borsh::to_vec(&result).unwrap());
+    assert_eq!(swap_result(&simulation).unwrap(), Some(result));
+}
+
+#[test]
+fn test_swap_result_ignores_other_programs() {
+    let simulation = simulation(&Pubkey::new_unique(), &[0; 32]);
+    assert_eq!(swap_result(&simulation).unwrap(), None);
+}
+
+#[test]
+fn test_swap_result_rejects_malformed_data() {
+    let simulation = simulation(&pumpfun_amm::id(), &[0; 3]);
+    assert!(swap_result(&simulation).is_err());
+}
@@ -46,15 +46,15 @@ pub static METADATA_PROGRAM: Pubkey =
     Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 
 // Derive Platform PDA for on curve token's instructions fee
-pub(crate) fn derive_global_volume_accumulator() -> (Pubkey, u8) {
+pub fn derive_global_volume_accumulator() -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"global_volume_accumulator"], &PUMP_FUN_LAUNCHPAD_PROGRAM)
 }
 
-pub(crate) fn derive_event_authority() -> (Pubkey, u8) {
+pub fn derive_event_authority() -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"__event_authority"], &PUMP_FUN_LAUNCHPAD_PROGRAM)
 }
 
-pub(crate) fn derive_global_config() -> (Pubkey, u8) {
+pub fn derive_global_config() -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"global_config"], &PUMPFUN_AMM_PROGRAM)
 }
 
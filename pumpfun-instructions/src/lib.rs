@@ -82,7 +82,7 @@ pub mod launchpad {
         let (associated_bounding_curve, _) =
             derive_associated_bounding_curve(&bounding_curve, mint);
         let (creator_vault, _) = derive_creator_vault(creator); // Creator's fee recipient for on curve operations
-        let (user_volume_accumulator, _) = derive_user_volume_accumulator(&payer);
+        let (user_volume_accumulator, _) = derive_user_volume_accumulator(payer);
         let accounts = vec![
             AccountMeta::new_readonly(GLOBAL, false),
             AccountMeta::new(PUMP_FUN_LAUNCHPAD_FEE_RECIPIENT, false),
@@ -110,6 +110,50 @@ pub mod launchpad {
             data,
         }
     }
+
+    #[derive(BorshSerialize, Debug)]
+    pub struct Sell {
+        pub amount: u64,
+        pub min_sol_output: u64,
+    }
+
+    pub fn sell(
+        payer: &Pubkey,
+        payer_ata: &Pubkey,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        instruction: Sell,
+    ) -> Instruction {
+        let discriminator = &[51u8, 230, 133, 164, 1, 127, 131, 173];
+        let (bounding_curve, _) = derive_bounding_curve(mint);
+        let (associated_bounding_curve, _) =
+            derive_associated_bounding_curve(&bounding_curve, mint);
+        let (creator_vault, _) = derive_creator_vault(creator);
+        let accounts = vec![
+            AccountMeta::new_readonly(GLOBAL, false),
+            AccountMeta::new(PUMP_FUN_LAUNCHPAD_FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(bounding_curve, false),
+            AccountMeta::new(associated_bounding_curve, false),
+            AccountMeta::new(*payer_ata, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new(creator_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(PUMP_FUN_LAUNCHPAD_EVENT_AUTHORITHY, false),
+            AccountMeta::new_readonly(PUMP_FUN_LAUNCHPAD_PROGRAM, false),
+        ];
+
+        let mut data = vec![];
+        data.extend(discriminator);
+        BorshSerialize::serialize(&instruction, &mut data).unwrap();
+
+        Instruction {
+            program_id: PUMP_FUN_LAUNCHPAD_PROGRAM,
+            accounts,
+            data,
+        }
+    }
 }
 
 pub mod amm {
@@ -177,3 +221,92 @@ pub mod amm {
         }
     }
 }
+
+pub mod events {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    pub use pumpfun_amm::SwapPerformed;
+
+    /// Extracts the router `SwapPerformed` events from the transaction log messages.
+    ///
+    /// Only `Program data:` lines logged while the router is the innermost invoked program are
+    /// decoded, so events forged by other programs in the same transaction are skipped.
+    pub fn decode_swap_events(logs: &[String]) -> Vec<SwapPerformed> {
+        let router = pumpfun_amm::id().to_string();
+        let mut invoked: Vec<&str> = vec![];
+        let mut events = vec![];
+
+        for log in logs {
+            let Some(log) = log.strip_prefix("Program ") else {
+                continue;
+            };
+
+            if let Some(data) = log.strip_prefix("data: ") {
+                if invoked.last() != Some(&router.as_str()) {
+                    continue;
+                }
+
+                let event = STANDARD
+                    .decode(data)
+                    .ok()
+                    .and_then(|data| SwapPerformed::decode(&data));
+                if let Some(event) = event {
+                    events.push(event);
+                }
+
+                continue;
+            }
+
+            let mut parts = log.split(' ');
+            match (parts.next(), parts.next()) {
+                (Some(program), Some("invoke")) => invoked.push(program),
+                (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+
+        events
+    }
+}
+
+pub mod return_data {
+    use anyhow::{anyhow, Error};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use borsh::BorshDeserialize;
+    use solana_client::rpc_response::RpcSimulateTransactionResult;
+
+    pub use pumpfun_amm::{QuoteResult, SwapResult};
+
+    /// Decodes the router return data of a `simulateTransaction` result, `None` if the
+    /// simulation has no return data or it was set by another program.
+    pub fn decode_return_data<T: BorshDeserialize>(
+        result: &RpcSimulateTransactionResult,
+    ) -> Result<Option<T>, Error> {
+        let Some(return_data) = &result.return_data else {
+            return Ok(None);
+        };
+
+        if return_data.program_id != pumpfun_amm::id().to_string() {
+            return Ok(None);
+        }
+
+        // Base64 is the only encoding the RPC uses for return data
+        let (data, _) = &return_data.data;
+        let data = STANDARD.decode(data)?;
+        let value = T::try_from_slice(&data)
+            .map_err(|err| anyhow!("invalid router return data: {}", err))?;
+
+        Ok(Some(value))
+    }
+
+    pub fn swap_result(result: &RpcSimulateTransactionResult) -> Result<Option<SwapResult>, Error> {
+        decode_return_data(result)
+    }
+
+    pub fn quote_result(
+        result: &RpcSimulateTransactionResult,
+    ) -> Result<Option<QuoteResult>, Error> {
+        decode_return_data(result)
+    }
+}